@@ -0,0 +1,52 @@
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{Achievement, Toast};
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::SteadyHand,
+        Achievement::FullCircle,
+        Achievement::Centurion,
+        Achievement::Loaded,
+    ];
+    pub const CENTURION_PINS: u32 = 100;
+    pub const LOADED_PINS: usize = 15;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::SteadyHand => "steady hand",
+            Achievement::FullCircle => "full circle",
+            Achievement::Centurion => "centurion",
+            Achievement::Loaded => "loaded",
+        }
+    }
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::SteadyHand => "clear a level without a close call",
+            Achievement::FullCircle => "clear the last built-in level",
+            Achievement::Centurion => "land 100 pins",
+            Achievement::Loaded => "clear a level that starts with more than 15 pins",
+        }
+    }
+}
+
+impl Toast {
+    const DURATION_SECONDS: f32 = 3.;
+    const FADE_SECONDS: f32 = 0.3;
+
+    pub fn new(achievement: Achievement) -> Toast {
+        Toast {
+            achievement,
+            age: Time::new::<second>(0.),
+        }
+    }
+    pub fn expired(&self) -> bool {
+        self.age > Time::new::<second>(Self::DURATION_SECONDS)
+    }
+    // fades in and out at either end
+    pub fn opacity(&self) -> f32 {
+        let age = self.age.get::<second>();
+        let remaining = Self::DURATION_SECONDS - age;
+        (age.min(remaining) / Self::FADE_SECONDS).clamp(0., 1.)
+    }
+}
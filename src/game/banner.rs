@@ -0,0 +1,67 @@
+use uom::si::{f32::Time, time::second};
+
+use crate::game::Banner;
+
+impl Banner {
+    const INFO_DURATION_SECONDS: f32 = 3.;
+
+    pub fn error(message: impl Into<String>) -> Banner {
+        Banner {
+            message: message.into(),
+            is_error: true,
+            age: Time::new::<second>(0.),
+        }
+    }
+    pub fn info(message: impl Into<String>) -> Banner {
+        Banner {
+            message: message.into(),
+            is_error: false,
+            age: Time::new::<second>(0.),
+        }
+    }
+    // everything the handlers for one event had to say, the same whichever order they said it in,
+    // with errors taking the place of anything else
+    pub fn combine(mut banners: Vec<Banner>) -> Option<Banner> {
+        if banners.iter().any(|banner| banner.is_error) {
+            banners.retain(|banner| banner.is_error);
+        }
+        let is_error = banners.first()?.is_error;
+        let mut messages: Vec<String> = banners.into_iter().map(|banner| banner.message).collect();
+        messages.sort();
+        messages.dedup();
+        Some(Banner {
+            message: messages.join(" / "),
+            is_error,
+            age: Time::new::<second>(0.),
+        })
+    }
+    // errors stay up until something replaces them, but info fades out on its own
+    pub fn expired(&self) -> bool {
+        !self.is_error && self.age > Time::new::<second>(Self::INFO_DURATION_SECONDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_banners_ignore_order() {
+        let combined = |messages: [&str; 2]| {
+            Banner::combine(messages.map(Banner::info).into()).map(|banner| banner.message)
+        };
+        let forward = combined(["Checkpoint", "New personal best!"]);
+        assert_eq!(forward, combined(["New personal best!", "Checkpoint"]));
+        assert_eq!(forward.as_deref(), Some("Checkpoint / New personal best!"));
+        assert!(Banner::combine(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn errors_replace_info_banners() {
+        let combined =
+            Banner::combine(vec![Banner::info("Checkpoint"), Banner::error("disk full")])
+                .expect("a banner");
+        assert!(combined.is_error);
+        assert_eq!(combined.message, "disk full");
+    }
+}
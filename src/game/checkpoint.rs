@@ -0,0 +1,85 @@
+use crate::game::{Checkpoint, PinFlying, PinInGun, Spinner};
+
+impl Checkpoint {
+    // pins still in flight go back in the gun, so they're fired again in the order they left it
+    pub fn new(
+        spinner: &Spinner,
+        pins_in_gun: &[PinInGun],
+        flying_pins: &[PinFlying],
+        lives: u32,
+    ) -> Checkpoint {
+        let mut pins_in_gun = pins_in_gun.to_vec();
+        pins_in_gun.extend(
+            flying_pins
+                .iter()
+                .rev()
+                .map(|pin| PinInGun { color: pin.color }),
+        );
+        Checkpoint {
+            spinner: spinner.clone(),
+            pins_in_gun,
+            lives,
+        }
+    }
+    // every life comes back, however many were left when the checkpoint was reached
+    pub fn restore(
+        &self,
+        spinner: &mut Spinner,
+        pins_in_gun: &mut Vec<PinInGun>,
+        flying_pins: &mut Vec<PinFlying>,
+        lives: &mut u32,
+    ) {
+        *spinner = self.spinner.clone();
+        *pins_in_gun = self.pins_in_gun.clone();
+        flying_pins.clear();
+        *lives = self.lives;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        ColorId,
+        test_utils::{blocker, fired, sector, spinner_landing_at},
+    };
+
+    #[test]
+    fn flying_pins_go_back_in_firing_order() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.5);
+        let checkpoint = Checkpoint::new(
+            &spinner,
+            &[PinInGun { color: ColorId(0) }],
+            &[fired(1), fired(2)],
+            0,
+        );
+        let mut pins_in_gun = checkpoint.pins_in_gun.clone();
+        let mut fired_colors = vec![];
+        while let Some(pin) = pins_in_gun.pop() {
+            fired_colors.push(pin.color.0);
+        }
+        assert_eq!(fired_colors, [1, 2, 0]);
+    }
+
+    #[test]
+    fn restore_gives_back_every_life() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.5);
+        let checkpoint = Checkpoint::new(&spinner, &[PinInGun { color: ColorId(0) }], &[], 2);
+
+        let mut played_spinner = spinner.clone();
+        played_spinner.pins.push(blocker(0.2, 0.05));
+        let mut pins_in_gun = vec![];
+        let mut flying_pins = vec![fired(1)];
+        let mut lives = 0;
+        checkpoint.restore(
+            &mut played_spinner,
+            &mut pins_in_gun,
+            &mut flying_pins,
+            &mut lives,
+        );
+        assert!(played_spinner.pins.is_empty());
+        assert_eq!(pins_in_gun.len(), 1);
+        assert!(flying_pins.is_empty());
+        assert_eq!(lives, 2);
+    }
+}
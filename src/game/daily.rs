@@ -0,0 +1,312 @@
+use std::{
+    collections::BTreeMap,
+    f32::consts::TAU,
+    fmt::{self, Display},
+};
+
+use macroquad::{color::Color, miniquad::date::now, rand::RandGenerator};
+use uom::si::{
+    angle::radian,
+    angular_velocity::radian_per_second,
+    f32::{Angle, AngularVelocity, Length},
+    length::meter,
+};
+
+use crate::game::{
+    CalendarDate, ColorId, DailyResult, Level, LevelPack, PaletteColor, PinInGun, PinOnSpinner,
+    Sector, Spinner, Sprites,
+};
+
+impl CalendarDate {
+    const SECONDS_PER_DAY: f64 = 86_400.;
+
+    pub fn today() -> CalendarDate {
+        Self::from_days((now() / Self::SECONDS_PER_DAY).floor() as i64)
+    }
+    // days since 1970-01-01 to a civil date, from
+    // https://howardhinnant.github.io/date_algorithms.html
+    fn from_days(days: i64) -> CalendarDate {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        CalendarDate { year, month, day }
+    }
+    pub fn seed(&self) -> u64 {
+        (self.year as u64) * 10_000 + u64::from(self.month) * 100 + u64::from(self.day)
+    }
+    pub fn pack_name(&self) -> String {
+        format!("daily-{self}")
+    }
+}
+
+impl Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl DailyResult {
+    // an unassisted run always beats an assisted one, whatever the score
+    pub fn beats(&self, other: &DailyResult) -> bool {
+        if self.assisted != other.assisted {
+            return other.assisted;
+        }
+        self.score > other.score || (self.score == other.score && self.time < other.time)
+    }
+}
+
+impl Level {
+    const DAILY_LEVEL_COUNT: usize = 5;
+    const DAILY_COLORS: [Color; 6] = [
+        Color::new(0.9, 0.16, 0.22, 1.),
+        Color::new(0., 0.47, 0.95, 1.),
+        Color::new(0., 0.89, 0.19, 1.),
+        Color::new(0.99, 0.98, 0., 1.),
+        Color::new(1., 0.63, 0., 1.),
+        Color::new(0.78, 0.48, 1., 1.),
+    ];
+    const BLOCKER_COLOR: Color = Color::new(0., 0., 0., 1.);
+    const BLOCKER_WIDTH_RADIANS: f32 = 0.14;
+    // room each pin in the gun is given on the spinner, so every daily level can be cleared
+    const DAILY_PIN_SPACING_RADIANS: f32 = 0.3;
+
+    // everyone gets the same levels on the same day, since the generator only sees the date
+    pub fn daily_pack(date: CalendarDate) -> LevelPack {
+        let rng = RandGenerator::new();
+        rng.srand(date.seed());
+        LevelPack {
+            theme: None,
+            sprites: Sprites::default(),
+            music: None,
+            bpm: None,
+            sounds: BTreeMap::new(),
+            levels: (0..Self::DAILY_LEVEL_COUNT)
+                .map(|difficulty| Self::generate(&rng, difficulty))
+                .collect(),
+        }
+    }
+    // pins that fit in the gaps a sector's blockers leave open
+    fn sector_room(sector: &Sector, blockers: &[PinOnSpinner]) -> usize {
+        let start = sector.angle_start.get::<radian>();
+        let stop = sector.angle_stop.get::<radian>();
+        let mut blocked: Vec<(f32, f32)> = blockers
+            .iter()
+            .flat_map(|blocker| {
+                let center = blocker.angular_position.get::<radian>();
+                let half_width = blocker.width.get::<radian>() / 2.;
+                // a blocker near the seam can reach into either end of the circle
+                [-TAU, 0., TAU]
+                    .map(|shift| (center + shift - half_width, center + shift + half_width))
+            })
+            .filter(|&(blocked_start, blocked_stop)| blocked_start < stop && blocked_stop > start)
+            .collect();
+        blocked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut room = 0;
+        let mut gap_start = start;
+        for (blocked_start, blocked_stop) in blocked {
+            if blocked_start > gap_start {
+                room += ((blocked_start - gap_start) / Self::DAILY_PIN_SPACING_RADIANS) as usize;
+            }
+            gap_start = gap_start.max(blocked_stop);
+        }
+        if stop > gap_start {
+            room += ((stop - gap_start) / Self::DAILY_PIN_SPACING_RADIANS) as usize;
+        }
+        room
+    }
+    // later levels get more colors, blockers, pins and speed
+    fn generate(rng: &RandGenerator, difficulty: usize) -> Level {
+        let color_count = 2 + difficulty / 2;
+        let mut color_choices: Vec<usize> = (0..Self::DAILY_COLORS.len()).collect();
+        for idx in 0..color_count {
+            let pick = rng.gen_range(idx, color_choices.len());
+            color_choices.swap(idx, pick);
+        }
+        let mut palette: Vec<PaletteColor> = color_choices[..color_count]
+            .iter()
+            .map(|&idx| PaletteColor(Self::DAILY_COLORS[idx]))
+            .collect();
+        let blocker_color = ColorId(palette.len() as u8);
+        palette.push(PaletteColor(Self::BLOCKER_COLOR));
+
+        // every color gets at least one sector, in a shuffled order around the spinner
+        let sector_count = rng.gen_range(color_count, color_count * 2 + 1);
+        let mut sector_colors: Vec<usize> = (0..sector_count)
+            .map(|idx| {
+                if idx < color_count {
+                    idx
+                } else {
+                    rng.gen_range(0, color_count)
+                }
+            })
+            .collect();
+        for idx in (1..sector_colors.len()).rev() {
+            sector_colors.swap(idx, rng.gen_range(0, idx + 1));
+        }
+        let weights: Vec<f32> = (0..sector_count).map(|_| rng.gen_range(1., 2.)).collect();
+        let total_weight: f32 = weights.iter().sum();
+        let mut sectors = Vec::with_capacity(sector_count);
+        let mut angle_start = 0.;
+        for (idx, weight) in weights.iter().enumerate() {
+            let angle_stop = if idx == sector_count - 1 {
+                TAU
+            } else {
+                angle_start + weight / total_weight * TAU
+            };
+            sectors.push(Sector {
+                color: ColorId(sector_colors[idx] as u8),
+                angle_start: Angle::new::<radian>(angle_start),
+                angle_stop: Angle::new::<radian>(angle_stop),
+            });
+            angle_start = angle_stop;
+        }
+
+        let blocker_count = rng.gen_range(0, difficulty + 1);
+        let pins: Vec<PinOnSpinner> = (0..blocker_count)
+            .map(|_| PinOnSpinner {
+                color: blocker_color,
+                angular_position: Angle::new::<radian>(rng.gen_range(0., TAU)),
+                length: Length::new::<meter>(1.),
+                width: Angle::new::<radian>(Self::BLOCKER_WIDTH_RADIANS),
+            })
+            .collect();
+
+        let direction = if rng.gen_range(0, 2) == 0 { 1. } else { -1. };
+        let speed = rng.gen_range(1., 1.2) + 0.15 * difficulty as f32;
+
+        // only as many pins of each color as its sectors have room for around the blockers
+        let mut room: Vec<usize> = vec![0; color_count];
+        for sector in sectors.iter() {
+            room[sector.color.0 as usize] += Self::sector_room(sector, &pins);
+        }
+        let pin_count = 6 + 2 * difficulty;
+        let mut pins_in_gun = Vec::with_capacity(pin_count);
+        for _ in 0..pin_count {
+            let total_room: usize = room.iter().sum();
+            if total_room == 0 {
+                break;
+            }
+            let mut pick = rng.gen_range(0, total_room);
+            let color_idx = room
+                .iter()
+                .position(|&color_room| {
+                    if pick < color_room {
+                        true
+                    } else {
+                        pick -= color_room;
+                        false
+                    }
+                })
+                .expect("pick is within the total room");
+            room[color_idx] -= 1;
+            pins_in_gun.push(PinInGun {
+                color: ColorId(color_idx as u8),
+            });
+        }
+
+        Level {
+            palette,
+            spinner: Spinner {
+                sectors,
+                angular_velocity: AngularVelocity::new::<radian_per_second>(direction * speed),
+                pins,
+                ..Spinner::default()
+            },
+            pins_in_gun,
+            sounds: BTreeMap::new(),
+            lives: 0,
+            checkpoints: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uom::si::{f32::Time, time::second};
+
+    use super::*;
+    use crate::game::test_utils::{blocker, date, sector};
+
+    #[test]
+    fn from_days_known_dates() {
+        assert_eq!(CalendarDate::from_days(0), date(1970, 1, 1));
+        assert_eq!(CalendarDate::from_days(11_016), date(2000, 2, 29));
+        assert_eq!(CalendarDate::from_days(11_017), date(2000, 3, 1));
+        assert_eq!(CalendarDate::from_days(-1), date(1969, 12, 31));
+        assert_eq!(CalendarDate::from_days(-719_468), date(0, 3, 1));
+    }
+
+    #[test]
+    fn seed_is_unique_per_date() {
+        assert_eq!(date(1970, 1, 1).seed(), 19_700_101);
+        assert_eq!(date(2000, 2, 29).seed(), 20_000_229);
+        assert_ne!(date(2000, 2, 29).seed(), date(2000, 3, 1).seed());
+        assert_ne!(date(1969, 12, 31).seed(), date(1970, 1, 1).seed());
+    }
+
+    #[test]
+    fn unassisted_daily_results_always_win() {
+        let result = |score: u32, assisted: bool| DailyResult {
+            score,
+            time: Time::new::<second>(60.),
+            runs: 1,
+            assisted,
+        };
+        assert!(result(500, false).beats(&result(400, false)));
+        assert!(result(100, false).beats(&result(900, true)));
+        assert!(!result(900, true).beats(&result(100, false)));
+        assert!(result(900, true).beats(&result(800, true)));
+    }
+
+    #[test]
+    fn sector_room_skips_blockers() {
+        let sector = sector(0, 0., 0.2);
+        let width = Level::BLOCKER_WIDTH_RADIANS / TAU;
+        assert_eq!(Level::sector_room(&sector, &[]), 4);
+        assert_eq!(Level::sector_room(&sector, &[blocker(0.1, width)]), 2);
+        // a blocker just before the seam still eats into the start of the sector
+        assert_eq!(Level::sector_room(&sector, &[blocker(0.999, width)]), 3);
+    }
+
+    #[test]
+    fn daily_pack_depends_only_on_the_date() {
+        let codes = |date: CalendarDate| -> Vec<String> {
+            Level::daily_pack(date)
+                .levels
+                .iter()
+                .map(Level::to_code)
+                .collect()
+        };
+        assert_eq!(codes(date(2026, 10, 19)), codes(date(2026, 10, 19)));
+        assert_ne!(codes(date(2026, 10, 19)), codes(date(2026, 10, 20)));
+    }
+
+    #[test]
+    fn daily_pins_fit_around_the_blockers() {
+        for day in 1..=28 {
+            for level in Level::daily_pack(date(2026, 2, day)).levels {
+                let mut room = vec![0; level.palette.len()];
+                for sector in level.spinner.sectors.iter() {
+                    room[sector.color.0 as usize] +=
+                        Level::sector_room(sector, &level.spinner.pins);
+                }
+                for pin in level.pins_in_gun.iter() {
+                    let color_room = &mut room[pin.color.0 as usize];
+                    assert!(*color_room > 0, "too many pins of {:?}", pin.color);
+                    *color_room -= 1;
+                }
+            }
+        }
+    }
+}
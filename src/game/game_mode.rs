@@ -0,0 +1,25 @@
+use crate::game::GameMode;
+
+impl GameMode {
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name {
+            "campaign" => Some(GameMode::Campaign),
+            "speedrun" => Some(GameMode::Speedrun),
+            "daily" => Some(GameMode::Daily),
+            "practice" => Some(GameMode::Practice),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Campaign => "campaign",
+            GameMode::Speedrun => "speedrun",
+            GameMode::Daily => "daily",
+            GameMode::Practice => "practice",
+        }
+    }
+    // practice is for trying things out, so it never makes the leaderboard and can undo shots
+    pub fn is_ranked(&self) -> bool {
+        *self != GameMode::Practice
+    }
+}
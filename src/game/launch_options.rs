@@ -0,0 +1,37 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::env;
+
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+use crate::game::{GameMode, LaunchOptions};
+
+impl LaunchOptions {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_env() -> LaunchOptions {
+        let mut options = LaunchOptions::default();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--code" => options.shared_level = args.next(),
+                "--mode" => {
+                    options.game_mode = args
+                        .next()
+                        .and_then(|name| GameMode::from_name(&name))
+                        .unwrap_or_default()
+                }
+                _ => options.level_pack = Some(arg),
+            }
+        }
+        options
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_env() -> LaunchOptions {
+        LaunchOptions {
+            game_mode: web::query_param("mode")
+                .and_then(|name| GameMode::from_name(&name))
+                .unwrap_or_default(),
+            level_pack: web::query_param("pack"),
+            shared_level: web::query_param("level"),
+        }
+    }
+}
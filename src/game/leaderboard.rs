@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+use serde_json::json;
+use uom::si::time::second;
+
+use crate::game::{GameMode, LeaderboardEntry};
+
+impl LeaderboardEntry {
+    pub const MAX_ENTRIES: usize = 10;
+    pub const MAX_NAME_LENGTH: usize = 16;
+    pub const DEFAULT_NAME: &'static str = "anonymous";
+
+    // campaigns and speedruns are races against the clock, while dailies rank by score with time
+    // breaking ties
+    fn compare(&self, other: &LeaderboardEntry, game_mode: GameMode) -> Ordering {
+        let by_time = self
+            .time
+            .partial_cmp(&other.time)
+            .unwrap_or(Ordering::Equal);
+        match game_mode {
+            GameMode::Daily => other.score.cmp(&self.score).then(by_time),
+            GameMode::Campaign | GameMode::Speedrun => by_time,
+            GameMode::Practice => unreachable!("practice runs never make the leaderboard"),
+        }
+    }
+    // where a new entry would land, if it makes the table at all; ties go below older entries
+    pub fn rank(&self, entries: &[LeaderboardEntry], game_mode: GameMode) -> Option<usize> {
+        let rank = entries
+            .iter()
+            .position(|entry| self.compare(entry, game_mode) == Ordering::Less)
+            .unwrap_or(entries.len());
+        (rank < Self::MAX_ENTRIES).then_some(rank)
+    }
+    pub fn insert(self, entries: &mut Vec<LeaderboardEntry>, game_mode: GameMode) -> Option<usize> {
+        let rank = self.rank(entries, game_mode)?;
+        entries.insert(rank, self);
+        entries.truncate(Self::MAX_ENTRIES);
+        Some(rank)
+    }
+    pub fn allowed_in_name(c: char) -> bool {
+        c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.')
+    }
+    pub fn to_csv(pack_name: &str, game_mode: GameMode, entries: &[LeaderboardEntry]) -> String {
+        let mut csv = String::from("pack,mode,rank,name,score,time_seconds,recorded_at,assisted\n");
+        for (rank, entry) in entries.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.3},{},{}\n",
+                csv_field(pack_name),
+                game_mode.name(),
+                rank + 1,
+                csv_field(&entry.name),
+                entry.score,
+                entry.time.get::<second>(),
+                entry.recorded_at as u64,
+                entry.assisted,
+            ));
+        }
+        csv
+    }
+    pub fn to_json(pack_name: &str, game_mode: GameMode, entries: &[LeaderboardEntry]) -> String {
+        let entries = entries
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                json!({
+                    "rank": rank + 1,
+                    "name": entry.name,
+                    "score": entry.score,
+                    "time": entry.time.get::<second>(),
+                    "recorded_at": entry.recorded_at as u64,
+                    "assisted": entry.assisted,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string_pretty(&json!({
+            "pack": pack_name,
+            "mode": game_mode.name(),
+            "entries": entries,
+        }))
+        .expect("leaderboards always serialize")
+    }
+}
+
+// pack names come from file names, so they might need quoting
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_utils::{entry, names};
+
+    #[test]
+    fn campaigns_and_speedruns_rank_by_time() {
+        let entries = vec![entry("a", 100, 30.), entry("b", 900, 60.)];
+        assert_eq!(
+            entry("c", 0, 45.).rank(&entries, GameMode::Campaign),
+            Some(1)
+        );
+        assert_eq!(
+            entry("c", 0, 20.).rank(&entries, GameMode::Speedrun),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn dailies_rank_by_score_then_time() {
+        let entries = vec![entry("a", 900, 60.), entry("b", 500, 30.)];
+        assert_eq!(
+            entry("c", 700, 90.).rank(&entries, GameMode::Daily),
+            Some(1)
+        );
+        assert_eq!(
+            entry("c", 900, 50.).rank(&entries, GameMode::Daily),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn ties_go_below_older_entries() {
+        let mut entries = vec![entry("a", 500, 30.)];
+        assert_eq!(
+            entry("b", 500, 30.).insert(&mut entries, GameMode::Campaign),
+            Some(1)
+        );
+        assert_eq!(names(&entries), ["a", "b"]);
+    }
+
+    #[test]
+    fn insert_keeps_only_the_top_entries() {
+        let mut entries: Vec<LeaderboardEntry> = (0..LeaderboardEntry::MAX_ENTRIES)
+            .map(|idx| entry(&idx.to_string(), 0, idx as f32 + 1.))
+            .collect();
+        assert_eq!(
+            entry("slow", 0, 99.).insert(&mut entries, GameMode::Campaign),
+            None
+        );
+        assert_eq!(
+            entry("fast", 0, 0.5).insert(&mut entries, GameMode::Campaign),
+            Some(0)
+        );
+        assert_eq!(entries.len(), LeaderboardEntry::MAX_ENTRIES);
+        assert_eq!(entries[0].name, "fast");
+        assert_eq!(entries.last().unwrap().name, "8");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("default"), "default");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
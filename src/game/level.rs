@@ -0,0 +1,409 @@
+use std::{collections::BTreeMap, path::Path};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bincode::Options;
+use macroquad::{color::Color, file::load_string};
+use serde_json::{Value, json};
+use uom::si::f32::{Angle, AngularVelocity, Length};
+
+use crate::game::{
+    LaunchOptions, Level, LevelPack, PackDefinition, PaletteColor, Sounds, Sprites, Theme,
+};
+
+impl Level {
+    const DEFAULT_PACK_NAME: &'static str = "default";
+    pub const SHARED_PACK_NAME: &'static str = "shared";
+    const CODE_VERSION: u8 = 3;
+
+    // pack_path is where the pack was loaded from, so a theme file can sit next to it
+    pub fn parse_pack(levels_str: &str, pack_path: Option<&str>) -> Result<LevelPack, String> {
+        let (theme, sprites, music, bpm, sounds, levels) =
+            match serde_json::from_str::<PackDefinition>(levels_str) {
+                Ok(PackDefinition::Levels(levels)) => (
+                    None,
+                    Sprites::default(),
+                    None,
+                    None,
+                    BTreeMap::new(),
+                    levels,
+                ),
+                Ok(PackDefinition::Pack {
+                    theme,
+                    sprites,
+                    music,
+                    bpm,
+                    sounds,
+                    levels,
+                }) => (theme, sprites, music, bpm, sounds, levels),
+                Err(e) => return Err(format!("Failed to parse level definitions: {e}")),
+            };
+        if levels.is_empty() {
+            return Err("No levels found".into());
+        }
+        if let Some(bpm) = bpm
+            && (bpm.is_nan() || bpm <= 0.)
+        {
+            return Err(format!("Invalid bpm {bpm}"));
+        }
+        let levels = levels
+            .into_iter()
+            .enumerate()
+            .map(|(level_idx, level)| {
+                let mut level = Self::from_json(level)
+                    .map_err(|e| format!("Failed to parse level {}: {e}", level_idx + 1))?;
+                level.sounds = Self::resolve_sounds(level.sounds, pack_path);
+                Ok(level)
+            })
+            .collect::<Result<Vec<Level>, String>>()?;
+        Ok(LevelPack {
+            theme: theme.map(|theme| Theme::resolve_path(&theme, pack_path)),
+            sprites: Sprites {
+                spinner_texture: sprites
+                    .spinner_texture
+                    .map(|path| Theme::resolve_path(&path, pack_path)),
+                gun_sprite: sprites
+                    .gun_sprite
+                    .map(|path| Theme::resolve_path(&path, pack_path)),
+                pin_sprite: sprites
+                    .pin_sprite
+                    .map(|path| Theme::resolve_path(&path, pack_path)),
+            },
+            music: music.map(|path| Theme::resolve_path(&path, pack_path)),
+            bpm,
+            sounds: Self::resolve_sounds(sounds, pack_path),
+            levels,
+        })
+    }
+    fn resolve_sounds(
+        sounds: BTreeMap<Sounds, String>,
+        pack_path: Option<&str>,
+    ) -> BTreeMap<Sounds, String> {
+        sounds
+            .into_iter()
+            .map(|(sound, path)| (sound, Theme::resolve_path(&path, pack_path)))
+            .collect()
+    }
+    fn from_json(mut level: Value) -> Result<Level, String> {
+        Self::assign_color_ids(&mut level)?;
+        let level = serde_json::from_value::<Level>(level).map_err(|e| e.to_string())?;
+        level.validate()?;
+        Ok(level)
+    }
+    // levels may spell colors out instead of using palette ids, so give each distinct color an id
+    fn assign_color_ids(level: &mut Value) -> Result<(), String> {
+        let mut palette: Vec<Color> = match level.get("palette") {
+            Some(palette) => serde_json::from_value::<Vec<PaletteColor>>(palette.clone())
+                .map_err(|e| format!("Invalid palette: {e}"))?
+                .into_iter()
+                .map(|color| color.0)
+                .collect(),
+            None => Vec::new(),
+        };
+        for pointer in ["/spinner/sectors", "/spinner/pins", "/pins_in_gun"] {
+            let Some(Value::Array(items)) = level.pointer_mut(pointer) else {
+                continue;
+            };
+            for item in items.iter_mut() {
+                let Some(color) = item.get_mut("color").filter(|color| color.is_object()) else {
+                    continue;
+                };
+                let raw = serde_json::from_value::<PaletteColor>(color.take())
+                    .map_err(|e| format!("Invalid color: {e}"))?
+                    .0;
+                let id = palette.iter().position(|c| *c == raw).unwrap_or_else(|| {
+                    palette.push(raw);
+                    palette.len() - 1
+                });
+                *color = Value::from(id);
+            }
+        }
+        if let Some(level) = level.as_object_mut() {
+            let palette: Vec<PaletteColor> = palette.into_iter().map(PaletteColor).collect();
+            level.insert(
+                "palette".into(),
+                serde_json::to_value(palette).expect("colors always serialize"),
+            );
+        }
+        Ok(())
+    }
+    fn validate(&self) -> Result<(), String> {
+        let color_ids = self
+            .spinner
+            .sectors
+            .iter()
+            .map(|sector| sector.color)
+            .chain(self.spinner.pins.iter().map(|pin| pin.color))
+            .chain(self.pins_in_gun.iter().map(|pin| pin.color));
+        for color_id in color_ids {
+            if color_id.0 as usize >= self.palette.len() {
+                return Err(format!("Color id {} is not in the palette", color_id.0));
+            }
+        }
+        for checkpoint in self.checkpoints.iter() {
+            if !(1..self.pins_in_gun.len()).contains(checkpoint) {
+                return Err(format!(
+                    "Checkpoint {checkpoint} is not partway through the {} pins in the gun",
+                    self.pins_in_gun.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+    // returns the pack name alongside the levels so progress can be saved per pack
+    pub async fn load_pack(options: &LaunchOptions) -> Result<(String, LevelPack), String> {
+        if let Some(level_str) = &options.shared_level {
+            let level = if level_str.trim_start().starts_with('{') {
+                serde_json::from_str::<Value>(level_str)
+                    .map_err(|e| e.to_string())
+                    .and_then(Self::from_json)
+                    .map_err(|e| format!("Failed to parse shared level: {e}"))?
+            } else {
+                Self::from_code(level_str)?
+            };
+            return Ok((
+                Self::SHARED_PACK_NAME.into(),
+                LevelPack {
+                    theme: None,
+                    sprites: Sprites::default(),
+                    music: None,
+                    bpm: None,
+                    sounds: BTreeMap::new(),
+                    levels: vec![level],
+                },
+            ));
+        }
+        match &options.level_pack {
+            Some(path) => {
+                let levels_str = load_string(path)
+                    .await
+                    .map_err(|e| format!("Failed to load {path}: {e}"))?;
+                let pack_name = Path::new(path.split(['?', '#']).next().unwrap_or(path))
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                Ok((pack_name, Self::parse_pack(&levels_str, Some(path))?))
+            }
+            None => Ok((
+                Self::DEFAULT_PACK_NAME.into(),
+                Self::parse_pack(include_str!("../../assets/levels.json"), None)?,
+            )),
+        }
+    }
+    // codes are a version byte and the bincode-encoded level, followed by a CRC32 of both, and
+    // leave out the sounds since those are paths on the sharer's machine
+    pub fn to_code(&self) -> String {
+        let mut bytes = vec![Self::CODE_VERSION];
+        bytes.extend(
+            bincode::DefaultOptions::new()
+                .serialize(&(
+                    &self.palette,
+                    &self.spinner,
+                    &self.pins_in_gun,
+                    self.lives,
+                    &self.checkpoints,
+                ))
+                .expect("levels always serialize"),
+        );
+        bytes.extend(crc32fast::hash(&bytes).to_le_bytes());
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+    pub fn from_code(code: &str) -> Result<Level, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(code.trim())
+            .map_err(|e| format!("Invalid level code: {e}"))?;
+        let Some((payload, checksum)) = bytes.split_last_chunk::<4>() else {
+            return Err("Invalid level code: too short".into());
+        };
+        if crc32fast::hash(payload) != u32::from_le_bytes(*checksum) {
+            return Err("Invalid level code: checksum mismatch".into());
+        }
+        let Some((&version, level_bytes)) = payload.split_first() else {
+            return Err("Invalid level code: too short".into());
+        };
+        let level = Self::decode_level(version, level_bytes)
+            .map_err(|e| format!("Invalid level code: {e}"))?;
+        level
+            .validate()
+            .map_err(|e| format!("Invalid level code: {e}"))?;
+        Ok(level)
+    }
+    // older codes stay playable
+    fn decode_level(version: u8, bytes: &[u8]) -> Result<Level, String> {
+        match version {
+            1 => Self::decode_level_v1(bytes),
+            // from before levels had lives and checkpoints
+            2 => {
+                let (palette, spinner, pins_in_gun) = bincode::DefaultOptions::new()
+                    .deserialize(bytes)
+                    .map_err(|e| e.to_string())?;
+                Ok(Level {
+                    palette,
+                    spinner,
+                    pins_in_gun,
+                    sounds: BTreeMap::new(),
+                    lives: 0,
+                    checkpoints: Vec::new(),
+                })
+            }
+            Self::CODE_VERSION => {
+                let (palette, spinner, pins_in_gun, lives, checkpoints) =
+                    bincode::DefaultOptions::new()
+                        .deserialize(bytes)
+                        .map_err(|e| e.to_string())?;
+                Ok(Level {
+                    palette,
+                    spinner,
+                    pins_in_gun,
+                    sounds: BTreeMap::new(),
+                    lives,
+                    checkpoints,
+                })
+            }
+            version => Err(format!("unsupported version {version}")),
+        }
+    }
+    // the first codes spelled out every color, so they go through the same palette building as
+    // json levels
+    fn decode_level_v1(bytes: &[u8]) -> Result<Level, String> {
+        type SectorV1 = (PaletteColor, Angle, Angle);
+        type PinOnSpinnerV1 = (PaletteColor, Angle, Length, Angle);
+        type SpinnerV1 = (
+            Vec<SectorV1>,
+            Angle,
+            AngularVelocity,
+            Vec<PinOnSpinnerV1>,
+            Length,
+        );
+        let ((sectors, angular_position, angular_velocity, pins, radius), pins_in_gun): (
+            SpinnerV1,
+            Vec<PaletteColor>,
+        ) = bincode::DefaultOptions::new()
+            .deserialize(bytes)
+            .map_err(|e| e.to_string())?;
+        let sectors: Vec<Value> = sectors
+            .iter()
+            .map(|(color, angle_start, angle_stop)| {
+                json!({"color": color, "angle_start": angle_start, "angle_stop": angle_stop})
+            })
+            .collect();
+        let pins: Vec<Value> = pins
+            .iter()
+            .map(|(color, angular_position, length, width)| {
+                json!({
+                    "color": color,
+                    "angular_position": angular_position,
+                    "length": length,
+                    "width": width,
+                })
+            })
+            .collect();
+        let pins_in_gun: Vec<Value> = pins_in_gun
+            .iter()
+            .map(|color| json!({ "color": color }))
+            .collect();
+        Self::from_json(json!({
+            "spinner": {
+                "sectors": sectors,
+                "angular_position": angular_position,
+                "angular_velocity": angular_velocity,
+                "pins": pins,
+                "radius": radius,
+            },
+            "pins_in_gun": pins_in_gun,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_utils::built_in_levels;
+
+    #[test]
+    fn codes_round_trip() {
+        for level in built_in_levels() {
+            let code = level.to_code();
+            let decoded = Level::from_code(&code).expect("own code decodes");
+            assert_eq!(decoded.to_code(), code);
+        }
+    }
+
+    #[test]
+    fn codes_leave_out_sounds() {
+        let mut level = built_in_levels().remove(0);
+        level.sounds.insert(Sounds::PinLand, "land.ogg".into());
+        let decoded = Level::from_code(&level.to_code()).expect("own code decodes");
+        assert!(decoded.sounds.is_empty());
+        assert_eq!(decoded.to_code(), built_in_levels()[0].to_code());
+    }
+
+    #[test]
+    fn corrupted_codes_are_rejected() {
+        let code = built_in_levels()[0].to_code();
+        let mut bytes = URL_SAFE_NO_PAD.decode(&code).unwrap();
+        bytes[1] ^= 0x01;
+        let error = Level::from_code(&URL_SAFE_NO_PAD.encode(&bytes)).unwrap_err();
+        assert!(error.contains("checksum"), "{error}");
+        let error = Level::from_code("AAA").unwrap_err();
+        assert!(error.contains("too short"), "{error}");
+    }
+
+    // a code copied out of an older build, holding the second built-in level
+    const V1_CODE: &str = "AQQUrkc_j8L1PgAAgD8AAIA_AAAAAIcWyT-kcH0_SOF6PwAAAAAAAIA_hxbJP4cWSUAAAAAACtdjP1yPQj4AAIA_hxZJQLTIlkAAAIA_AACAPwAAgD8AAIA_tMiWQFYOyUAAAAAAzcyMPwQAAAAAAAAAAAAAAAAAAIA_AAAAAAAAgD8pXA8-AAAAAAAAAAAAAAAAAACAP4cWyT8AAIA_KVwPPgAAAAAAAAAAAAAAAAAAgD-HFklAAACAPylcDz4AAAAAAAAAAAAAAAAAAIA_tMiWQAAAgD8pXA8-AAAAQAykcH0_SOF6PwAAAAAAAIA_FK5HP4_C9T4AAIA_AACAPwAAgD8AAIA_AACAPwAAgD8AAIA_AACAPwAAgD8AAIA_pHB9P0jhej8AAAAAAACAPxSuRz-PwvU-AACAPwAAgD8AAAAACtdjP1yPQj4AAIA_AACAPwAAgD8AAIA_AACAPxSuRz-PwvU-AACAPwAAgD8AAAAACtdjP1yPQj4AAIA_AAAAAArXYz9cj0I-AACAP6RwfT9I4Xo_AAAAAAAAgD-iiQdt";
+
+    #[test]
+    fn v1_codes_decode() {
+        let v1 = Level::from_code(V1_CODE).expect("v1 code decodes");
+        assert_eq!(v1.pins_in_gun.len(), built_in_levels()[1].pins_in_gun.len());
+        assert_eq!(v1.to_code(), built_in_levels()[1].to_code());
+    }
+
+    // a code from before levels had lives and checkpoints, holding the third built-in level
+    const V2_CODE: &str = "AgVmZmY_CtcjPq5HYT4AAIA_AACAP65HIT8AAAAAAACAPxSuRz-PwvU-AACAPwAAgD8AAAAACtdjP1yPQj4AAIA_AAAAAAAAAAAAAAAAAACAPwQAAAAAAIcWyT8BhxbJP4cWSUAChxZJQLTIlkADtMiWQFYOyUAAAAAAmpmZPwQEAAAAAAAAgD8pXA8-BIcWyT8AAIA_KVwPPgSHFklAAACAPylcDz4EtMiWQAAAgD8pXA8-AAAAQBQAAgEAAwEDAgACAQMDAAEAAgEDAhiFPzY";
+
+    #[test]
+    fn v2_codes_decode() {
+        let v2 = Level::from_code(V2_CODE).expect("v2 code decodes");
+        assert_eq!(v2.lives, 0);
+        assert!(v2.checkpoints.is_empty());
+        let mut level = built_in_levels().remove(2);
+        level.checkpoints.clear();
+        assert_eq!(v2.to_code(), level.to_code());
+    }
+
+    #[test]
+    fn codes_keep_lives_and_checkpoints() {
+        let mut level = built_in_levels().remove(2);
+        level.lives = 2;
+        let decoded = Level::from_code(&level.to_code()).expect("own code decodes");
+        assert_eq!(decoded.lives, 2);
+        assert_eq!(decoded.checkpoints, level.checkpoints);
+    }
+
+    #[test]
+    fn assign_color_ids_reuses_matching_colors() {
+        let red = json!({"r": 1.0, "g": 0.0, "b": 0.0, "a": 1.0});
+        let blue = json!({"r": 0.0, "g": 0.0, "b": 1.0, "a": 1.0});
+        let mut level = json!({
+            "palette": [blue],
+            "spinner": {
+                "sectors": [{"color": red}, {"color": 0}],
+                "pins": [{"color": blue}],
+            },
+            "pins_in_gun": [{"color": red}, {"color": blue}],
+        });
+        Level::assign_color_ids(&mut level).unwrap();
+        assert_eq!(level["palette"].as_array().unwrap().len(), 2);
+        assert_eq!(level["spinner"]["sectors"][0]["color"], 1);
+        assert_eq!(level["spinner"]["sectors"][1]["color"], 0);
+        assert_eq!(level["spinner"]["pins"][0]["color"], 0);
+        assert_eq!(level["pins_in_gun"][0]["color"], 1);
+        assert_eq!(level["pins_in_gun"][1]["color"], 0);
+    }
+
+    #[test]
+    fn assign_color_ids_rejects_bad_colors() {
+        let mut level = json!({"pins_in_gun": [{"color": {"r": "red"}}]});
+        assert!(Level::assign_color_ids(&mut level).is_err());
+    }
+}
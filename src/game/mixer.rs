@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, HashMap};
+
+use macroquad::audio::{
+    PlaySoundParams, Sound, load_sound, play_sound, set_sound_volume, stop_sound,
+};
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{LandPitch, Mixer, Settings, SoundSource, Sounds};
+
+impl Mixer {
+    const DUCK_SECONDS: f32 = 2.5;
+    const DUCK_FADE_SECONDS: f32 = 0.5;
+    const DUCK_VOLUME: f32 = 0.25;
+
+    fn sfx_volume(settings: &Settings) -> f32 {
+        if settings.muted {
+            return 0.;
+        }
+        settings.master_volume * settings.sfx_volume
+    }
+    // music drops while ducked and fades back up over the last part of the duck
+    fn music_volume(&self, settings: &Settings) -> f32 {
+        if settings.muted {
+            return 0.;
+        }
+        let remaining = self.duck.get::<second>();
+        let duck_factor = if remaining <= 0. {
+            1.
+        } else if remaining < Self::DUCK_FADE_SECONDS {
+            let fade = 1. - remaining / Self::DUCK_FADE_SECONDS;
+            Self::DUCK_VOLUME + (1. - Self::DUCK_VOLUME) * fade
+        } else {
+            Self::DUCK_VOLUME
+        };
+        settings.master_volume * settings.music_volume * duck_factor
+    }
+    // picks where each sound comes from and loads any that haven't been tried yet, with pitched
+    // copies of the landing sound only once the landing pitch setting asks for them
+    pub async fn select_sounds(
+        &mut self,
+        pack_sounds: &BTreeMap<Sounds, String>,
+        level_sounds: &BTreeMap<Sounds, String>,
+        settings: &Settings,
+    ) -> Vec<String> {
+        let sources: HashMap<Sounds, SoundSource> = Sounds::ALL
+            .into_iter()
+            .map(|sound| (sound, SoundSource::select(sound, pack_sounds, level_sounds)))
+            .collect();
+        let mut errors = Vec::new();
+        if sources != self.sources {
+            self.sources = sources;
+            errors = self.sound_data.load(self.sources.values()).await;
+        }
+        if settings.land_pitch != LandPitch::Off
+            && let Some(source) = self.sources.get(&Sounds::PinLand)
+            && let Err(e) = self.sound_data.load_pitched(source).await
+        {
+            errors.push(e);
+        }
+        errors
+    }
+    pub fn play(&mut self, sound: Sounds, settings: &Settings) {
+        if let Sounds::LoseLevel | Sounds::WinGame = sound {
+            self.duck = Time::new::<second>(Self::DUCK_SECONDS);
+        }
+        // anything that failed to load plays as silence
+        let Some(sound) = self
+            .sources
+            .get(&sound)
+            .and_then(|source| self.sound_data.get(source))
+        else {
+            return;
+        };
+        Self::play_effect(sound, settings);
+    }
+    // falls back to the normal pitch if the sound couldn't be repitched
+    pub fn play_pitched(&mut self, sound: Sounds, step: usize, settings: &Settings) {
+        match self
+            .sources
+            .get(&sound)
+            .and_then(|source| self.sound_data.get_pitched(source, step))
+        {
+            Some(pitched) => Self::play_effect(pitched, settings),
+            None => self.play(sound, settings),
+        }
+    }
+    fn play_effect(sound: &Sound, settings: &Settings) {
+        play_sound(
+            sound,
+            PlaySoundParams {
+                looped: false,
+                volume: Self::sfx_volume(settings),
+            },
+        );
+    }
+    pub fn update(&mut self, dt: Time, settings: &Settings) {
+        self.duck = (self.duck - dt).max(Time::new::<second>(0.));
+        if let Some(music) = &self.music {
+            set_sound_volume(music, self.music_volume(settings));
+            self.music_time += dt;
+        }
+    }
+    pub fn music_playing(&self) -> bool {
+        self.music.is_some()
+    }
+    // how far through the current beat the music is, from 0 on the beat up to 1 just before the
+    // next
+    pub fn beat_phase(&self, bpm: f32) -> f32 {
+        (self.music_time.get::<second>() * bpm / 60.).fract()
+    }
+    // seconds to the nearest beat, early or late
+    pub fn beat_offset(&self, bpm: f32) -> f32 {
+        let phase = self.beat_phase(bpm);
+        phase.min(1. - phase) * 60. / bpm
+    }
+    pub fn loaded_music(&self) -> Option<&str> {
+        self.loaded_music.as_deref()
+    }
+    // swaps out the looping music, remembering the source even if it fails so it isn't retried
+    pub async fn load_music(
+        &mut self,
+        source: Option<String>,
+        settings: &Settings,
+    ) -> Result<(), String> {
+        if let Some(music) = self.music.take() {
+            stop_sound(&music);
+        }
+        self.loaded_music = source.clone();
+        self.music_time = Time::new::<second>(0.);
+        let Some(path) = source else {
+            return Ok(());
+        };
+        let music = load_sound(&path)
+            .await
+            .map_err(|e| format!("Failed to load music {path}: {e}"))?;
+        play_sound(
+            &music,
+            PlaySoundParams {
+                looped: true,
+                volume: self.music_volume(settings),
+            },
+        );
+        self.music = Some(music);
+        Ok(())
+    }
+}
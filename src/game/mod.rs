@@ -1,20 +1,46 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     fmt::Debug,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
 };
+#[cfg(not(target_arch = "wasm32"))]
+use std::{path::PathBuf, time::SystemTime};
 
-use macroquad::{
-    audio::{Sound, load_sound_from_bytes},
-    prelude::*,
-};
-use serde::Deserialize;
-use uom::si::f32::{Angle, AngularVelocity, Length, Velocity};
+use macroquad::{audio::Sound, prelude::*};
+use serde::{Deserialize, Serialize};
+use uom::si::f32::{Angle, AngularVelocity, Length, Time, Velocity};
 
+mod achievements;
+mod banner;
+mod checkpoint;
+mod daily;
+mod game_mode;
+mod launch_options;
+mod leaderboard;
+mod level;
+mod mixer;
+mod palette;
+mod particles;
 mod pin_flying;
+mod save_data;
 mod sector;
+mod settings;
+mod sound_data;
 mod spinner;
+mod spinner_mesh;
 mod state;
+mod stats;
+mod storage;
+#[cfg(test)]
+mod test_utils;
+mod theme;
+mod transition;
+mod ui;
 mod utils;
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
 #[derive(Debug)]
 pub struct GameState<'a> {
@@ -25,53 +51,349 @@ pub struct GameState<'a> {
     spinner: Spinner,
     pin_gun: PinGun,
     flying_pins: Vec<PinFlying>,
+    game_mode: GameMode,
+    daily_date: Option<CalendarDate>,
+    pack_name: String,
+    // the levels bundled with the game, rather than a pack that happens to share its name
+    built_in_pack: bool,
     levels: Vec<Level>,
     level_idx: usize,
     level_state: LevelState,
-    sound_data: SoundData,
+    level_stats: Vec<LevelStats>,
+    transition: Option<Transition>,
+    spinner_mesh: SpinnerMesh,
+    color_map: ColorMap,
+    splits: Vec<Time>,
+    particles: ParticleSystem,
+    settings_menu: Option<usize>,
+    achievements_open: bool,
+    leaderboard_open: bool,
+    name_entry: Option<NameEntry>,
+    // only runs from the first level count for the leaderboard
+    full_run: bool,
+    toasts: VecDeque<Toast>,
+    // no close calls since the level was last started
+    clean_attempt: bool,
+    audio_unlocked: bool,
+    mixer: Mixer,
+    save_data: SaveData,
+    banner: Option<Banner>,
+    pack_theme: Option<String>,
+    pack_sprites: Sprites,
+    pack_music: Option<String>,
+    pack_sounds: BTreeMap<Sounds, String>,
+    pack_bpm: Option<f32>,
+    combo: usize,
+    lives: u32,
+    checkpoint: Option<Checkpoint>,
+    event_handlers: Vec<EventHandler<'a>>,
+    // handlers leave banners and saves here for emit to deal with once they've all run
+    event_banners: Vec<Banner>,
+    save_after_event: bool,
+    shot_history: Vec<ShotSnapshot>,
+    theme: Theme,
+    theme_assets: ThemeAssets,
+    loaded_theme: Option<String>,
+    fonts: HashMap<String, &'static Font>,
+    ui: Ui,
+    physics_lag: Time,
+    #[cfg(not(target_arch = "wasm32"))]
+    level_watcher: Option<LevelWatcher>,
+}
+
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    game_mode: GameMode,
+    level_pack: Option<String>,
+    shared_level: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct SaveData {
+    settings: Settings,
+    progress: HashMap<String, usize>,
+    personal_bests: HashMap<String, Vec<Time>>,
+    // runs with assists on are timed against each other, never against the unassisted bests
+    assisted_personal_bests: HashMap<String, Vec<Time>>,
+    achievements: BTreeSet<Achievement>,
+    pins_landed: u32,
+    // high scores by pack, then by mode
+    leaderboards: HashMap<String, HashMap<GameMode, Vec<LeaderboardEntry>>>,
+    last_name: String,
+    // keyed by date, like 2025-06-01
+    daily_results: BTreeMap<String, DailyResult>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct LeaderboardEntry {
+    name: String,
+    score: u32,
+    time: Time,
+    // seconds since the unix epoch
+    recorded_at: f64,
+    #[serde(default)]
+    assisted: bool,
+}
+
+// a new record waiting for the player to type their name
+#[derive(Debug)]
+struct NameEntry {
+    rank: usize,
+    entry: LeaderboardEntry,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Achievement {
+    SteadyHand,
+    FullCircle,
+    Centurion,
+    Loaded,
+}
+
+// a short note in the corner when an achievement unlocks
+#[derive(Debug)]
+struct Toast {
+    achievement: Achievement,
+    age: Time,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+struct Settings {
+    particles: bool,
+    color_glyphs: bool,
+    palette: Palette,
+    theme: Option<String>,
+    landing_preview: bool,
+    aim_assist: bool,
+    aim_assist_window: f32,
+    master_volume: f32,
+    sfx_volume: f32,
+    music_volume: f32,
+    muted: bool,
+    land_pitch: LandPitch,
+    rhythm_mode: bool,
+    spinner_speed: f32,
+    pin_speed: f32,
+    forgiving_blockers: bool,
+    extra_lives: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SettingsItem {
+    Particles,
+    ColorGlyphs,
+    Palette,
+    Theme,
+    LandingPreview,
+    AimAssist,
+    AimAssistWindow,
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    LandPitch,
+    RhythmMode,
+    SpinnerSpeed,
+    PinSpeed,
+    ForgivingBlockers,
+    ExtraLives,
+}
+
+// what sets the pitch of each landing sound
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum LandPitch {
+    Off,
+    #[default]
+    Combo,
+    Color,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+enum Palette {
+    #[default]
+    Original,
+    OkabeIto,
+    TolVibrant,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ColorMap {
+    level_colors: Vec<Color>,
+    sector_colors: Vec<ColorId>,
+    theme_colors: Vec<Color>,
+    palette: Palette,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Theme {
+    background: Background,
+    #[serde(with = "SerdeColor")]
+    gun_color: Color,
+    #[serde(with = "SerdeColor")]
+    text_color: Color,
+    #[serde(with = "SerdeColor")]
+    highlight_color: Color,
+    #[serde(default)]
+    palette: Vec<PaletteColor>,
+    #[serde(flatten)]
+    sprites: Sprites,
+    font: Option<String>,
+}
+
+// image paths that themes and packs can both set, with the pack's taking priority
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Sprites {
+    spinner_texture: Option<String>,
+    gun_sprite: Option<String>,
+    pin_sprite: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Background {
+    Solid {
+        #[serde(with = "SerdeColor")]
+        color: Color,
+    },
+    Gradient {
+        #[serde(with = "SerdeColor")]
+        top: Color,
+        #[serde(with = "SerdeColor")]
+        bottom: Color,
+    },
+    Image {
+        path: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct ThemeAssets {
+    background: Option<Texture2D>,
+    spinner_texture: Option<Texture2D>,
+    gun_sprite: Option<Texture2D>,
+    pin_sprite: Option<Texture2D>,
+    font: Option<&'static Font>,
+}
+
+// screen-space layout in pixels, scaled from the target window size and kept inside the safe area
+#[derive(Debug, Default)]
+struct Ui {
+    screen: Vec2,
+    insets: Insets,
+    scale: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Insets {
+    top: f32,
+    right: f32,
+    bottom: f32,
+    left: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    BottomLeft,
+    Bottom,
+}
+
+#[derive(Debug, Default)]
+struct ParticleSystem {
+    enabled: bool,
+    particles: Vec<Particle>,
+    shake: f32,
+}
+
+#[derive(Debug)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    color: Color,
+    size: f32,
+    gravity_scale: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum GameMode {
+    #[default]
+    Campaign,
+    Speedrun,
+    Daily,
+    Practice,
+}
+
+// how a finished speedrun compares with the saved personal best, which goes stale once the pack
+// changes how many levels it has
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RunComparison {
+    FirstRun,
+    Faster,
+    Slower,
+    StaleBest,
+}
+
+// a day in utc, which picks the daily challenge
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CalendarDate {
+    year: i64,
+    month: u32,
+    day: u32,
+}
+
+// the best finished run of a daily challenge, and how many runs were finished that day
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct DailyResult {
+    score: u32,
+    time: Time,
+    runs: u32,
+    #[serde(default)]
+    assisted: bool,
 }
 
 #[derive(Debug)]
+struct Banner {
+    message: String,
+    is_error: bool,
+    age: Time,
+}
+
+// each source loads once, when the first level that uses it starts, and any that fail to load stay
+// silent
+#[derive(Debug, Default)]
 struct SoundData {
-    pin_fire: Sound,
-    pin_land: Sound,
-    lose_level: Sound,
-    next_level: Sound,
-    win_level: Sound,
-    win_game: Sound,
-}
-
-impl SoundData {
-    async fn load() -> SoundData {
-        let pin_fire = load_sound_from_bytes(include_bytes!("../../assets/pin-fire.ogg"))
-            .await
-            .unwrap();
-        let pin_land = load_sound_from_bytes(include_bytes!("../../assets/pin-land.ogg"))
-            .await
-            .unwrap();
-        let lose_level = load_sound_from_bytes(include_bytes!("../../assets/lose-level.ogg"))
-            .await
-            .unwrap();
-        let next_level = load_sound_from_bytes(include_bytes!("../../assets/next-level.ogg"))
-            .await
-            .unwrap();
-        let win_level = load_sound_from_bytes(include_bytes!("../../assets/win-level.ogg"))
-            .await
-            .unwrap();
-        let win_game = load_sound_from_bytes(include_bytes!("../../assets/win-game.ogg"))
-            .await
-            .unwrap();
-        SoundData {
-            pin_fire,
-            pin_land,
-            lose_level,
-            next_level,
-            win_level,
-            win_game,
-        }
-    }
+    loaded: HashMap<SoundSource, Option<Sound>>,
+    pitched: HashMap<SoundSource, Vec<Sound>>,
 }
 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum SoundSource {
+    BuiltIn(Sounds),
+    File(String),
+}
+
+// sound effects and looping pack music, with the music ducked for a moment under big events
+#[derive(Debug, Default)]
+struct Mixer {
+    sound_data: SoundData,
+    sources: HashMap<Sounds, SoundSource>,
+    music: Option<Sound>,
+    loaded_music: Option<String>,
+    music_time: Time,
+    duck: Time,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
 enum Sounds {
     PinFire,
     PinLand,
@@ -103,13 +425,79 @@ impl<'a> DerefMut for GlWrapper<'a> {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+struct LevelWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    time_since_poll: Time,
+}
+
+#[derive(Debug)]
+struct LevelPack {
+    theme: Option<String>,
+    sprites: Sprites,
+    music: Option<String>,
+    bpm: Option<f32>,
+    sounds: BTreeMap<Sounds, String>,
+    levels: Vec<Level>,
+}
+
+// packs are either a bare list of levels or an object that also sets a theme, music or sounds
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a list of levels, or an object with levels and optional theme, sprites, music, bpm and sounds"
+)]
+enum PackDefinition {
+    Levels(Vec<serde_json::Value>),
+    Pack {
+        theme: Option<String>,
+        #[serde(flatten)]
+        sprites: Sprites,
+        music: Option<String>,
+        bpm: Option<f32>,
+        #[serde(default)]
+        sounds: BTreeMap<Sounds, String>,
+        levels: Vec<serde_json::Value>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct Level {
+    palette: Vec<PaletteColor>,
     spinner: Spinner,
     pins_in_gun: Vec<PinInGun>,
+    #[serde(default)]
+    sounds: BTreeMap<Sounds, String>,
+    // spare pins the level hands out on its own, on top of any from the settings
+    #[serde(default)]
+    lives: u32,
+    // how many pins have to land before each checkpoint
+    #[serde(default)]
+    checkpoints: Vec<usize>,
 }
 
-#[derive(Deserialize)]
+// where a lost level picks back up from, with any pins still in flight put back in the gun
+#[derive(Clone, Debug)]
+struct Checkpoint {
+    spinner: Spinner,
+    pins_in_gun: Vec<PinInGun>,
+    lives: u32,
+}
+
+// everything a shot can change, taken just before it's fired so practice mode can undo it
+#[derive(Debug)]
+struct ShotSnapshot {
+    spinner: Spinner,
+    pins_in_gun: Vec<PinInGun>,
+    flying_pins: Vec<PinFlying>,
+    lives: u32,
+    checkpoint: Option<Checkpoint>,
+    stats: LevelStats,
+}
+
+#[derive(Deserialize, Serialize)]
 #[serde(remote = "Color")]
 pub struct SerdeColor {
     r: f32,
@@ -118,6 +506,35 @@ pub struct SerdeColor {
     a: f32,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+struct PaletteColor(#[serde(with = "SerdeColor")] Color);
+
+// index into the level palette; pins match sectors by this, never by the displayed color
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(transparent)]
+struct ColorId(u8);
+
+#[derive(Debug)]
+struct Transition {
+    old_spinner: Spinner,
+    old_color_map: ColorMap,
+    old_level_idx: usize,
+    pin_count: usize,
+    elapsed: Time,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct LevelStats {
+    time: Time,
+    pins_fired: u32,
+    pins_landed: u32,
+    retries: u32,
+    close_calls: u32,
+    on_beat: u32,
+    // set once any assist was on while the level was played
+    assisted: bool,
+}
+
 #[derive(Debug, PartialEq, Default)]
 enum LevelState {
     #[default]
@@ -126,46 +543,118 @@ enum LevelState {
     Lost,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct Spinner {
     sectors: Vec<Sector>,
     angular_position: Angle,
     angular_velocity: AngularVelocity,
     pins: Vec<PinOnSpinner>,
     radius: Length,
+    // blockers only count as hit within this fraction of their width, for the forgiving assist
+    #[serde(skip, default = "Spinner::full_blocker_width")]
+    blocker_hit_width: f32,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Debug, Default)]
+struct SpinnerMesh {
+    key: Vec<u32>,
+    face_batches: Vec<MeshBatch>,
+    pin_batches: Vec<MeshBatch>,
+    face_texture: Option<Texture2D>,
+    pin_texture: Option<Texture2D>,
+}
+
+// as much of the spinner mesh as fits in one draw call
+#[derive(Debug, Default)]
+struct MeshBatch {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 struct Sector {
-    #[serde(with = "SerdeColor")]
-    color: Color,
+    color: ColorId,
     angle_start: Angle,
     angle_stop: Angle,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 struct PinOnSpinner {
-    #[serde(with = "SerdeColor")]
-    color: Color,
+    color: ColorId,
     angular_position: Angle,
     length: Length,
     width: Angle,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct PinFlying {
-    color: Color,
+    color: ColorId,
     vertical_position: Length,
     vertical_velocity: Velocity,
 }
 
+// what a flying pin hit, checked the same way whether the game is playing or predicting
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Collision {
+    Landed { sector: usize, margin: Angle },
+    WrongSector,
+    Blocked,
+}
+
+// things that happen in play, which every subscribed handler reacts to
+#[derive(Clone, Debug)]
+enum GameEvent {
+    PinFired {
+        on_beat: bool,
+    },
+    // combo is how many pins landed in a row before this one
+    PinLanded {
+        sector: usize,
+        color: ColorId,
+        margin: Angle,
+        combo: usize,
+    },
+    // the pin hit a blocker or the wrong sector and broke
+    PinBlocked {
+        pin: PinFlying,
+    },
+    LevelStarted {
+        retry: bool,
+    },
+    // a spare life was spent instead of losing the level
+    LifeLost,
+    LevelWon,
+    LevelLost,
+    GameWon,
+}
+
+// handlers run in no particular order, so each one only reads state that no other handler changes,
+// and banners and saves go through emit instead of being written directly
+type EventHandler<'a> = fn(&mut GameState<'a>, &GameEvent);
+
+// arc is the span of the sector or blocker that was hit, in the spinner's own frame
+#[derive(Debug)]
+struct Contact {
+    pin_idx: usize,
+    pin: PinFlying,
+    collision: Collision,
+    arc: Range<Angle>,
+}
+
+// where the next pin would end up, with angles in the spinner's own frame
+#[derive(Clone, Debug)]
+struct Prediction {
+    collision: Collision,
+    angle: Angle,
+    arc: Range<Angle>,
+}
+
 #[derive(Debug, Default)]
 struct PinGun {
     pins: Vec<PinInGun>,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 struct PinInGun {
-    #[serde(with = "SerdeColor")]
-    color: Color,
+    color: ColorId,
 }
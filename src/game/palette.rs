@@ -0,0 +1,80 @@
+use macroquad::color::{Color, colors};
+
+use crate::game::{ColorId, ColorMap, Level, Palette, Theme};
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Original, Palette::OkabeIto, Palette::TolVibrant];
+
+    // Okabe & Ito, "Color Universal Design", with white in place of black
+    const OKABE_ITO: [Color; 8] = [
+        Color::from_hex(0xE69F00),
+        Color::from_hex(0x56B4E9),
+        Color::from_hex(0x009E73),
+        Color::from_hex(0xF0E442),
+        Color::from_hex(0x0072B2),
+        Color::from_hex(0xD55E00),
+        Color::from_hex(0xCC79A7),
+        Color::from_hex(0xFFFFFF),
+    ];
+    // Paul Tol's "vibrant" qualitative scheme
+    const TOL_VIBRANT: [Color; 7] = [
+        Color::from_hex(0xEE7733),
+        Color::from_hex(0x0077BB),
+        Color::from_hex(0x33BBEE),
+        Color::from_hex(0xEE3377),
+        Color::from_hex(0xCC3311),
+        Color::from_hex(0x009988),
+        Color::from_hex(0xBBBBBB),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Original => "original",
+            Palette::OkabeIto => "deuteranopia-safe",
+            Palette::TolVibrant => "high contrast",
+        }
+    }
+    fn colors(&self) -> Option<&'static [Color]> {
+        match self {
+            Palette::Original => None,
+            Palette::OkabeIto => Some(&Self::OKABE_ITO),
+            Palette::TolVibrant => Some(&Self::TOL_VIBRANT),
+        }
+    }
+}
+
+impl ColorMap {
+    // only sector colors take part in matching, so blocker pins keep their original color
+    pub fn new(level: &Level, palette: Palette, theme: &Theme) -> ColorMap {
+        let mut sector_colors: Vec<ColorId> = Vec::new();
+        for sector in level.spinner.sectors.iter() {
+            if !sector_colors.contains(&sector.color) {
+                sector_colors.push(sector.color);
+            }
+        }
+        ColorMap {
+            level_colors: level.palette.iter().map(|color| color.0).collect(),
+            sector_colors,
+            theme_colors: theme.palette.iter().map(|color| color.0).collect(),
+            palette,
+        }
+    }
+    pub fn glyph(&self, color: ColorId) -> Option<usize> {
+        self.sector_colors.iter().position(|c| *c == color)
+    }
+    // an accessibility palette wins over the theme's, which wins over the level's own colors
+    pub fn display(&self, color: ColorId) -> Color {
+        let colors = self
+            .palette
+            .colors()
+            .or((!self.theme_colors.is_empty()).then_some(&self.theme_colors[..]));
+        match (colors, self.glyph(color)) {
+            (Some(colors), Some(glyph)) => colors[glyph % colors.len()],
+            _ => self
+                .level_colors
+                .get(color.0 as usize)
+                .copied()
+                .unwrap_or(colors::BLACK),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use macroquad::{prelude::*, rand::gen_range};
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{Particle, ParticleSystem};
+
+impl ParticleSystem {
+    const GRAVITY: f32 = -9.;
+    const SHAKE_DECAY_PER_SECOND: f32 = 0.75;
+    const CONFETTI_COLORS: [Color; 6] = [
+        Color::from_hex(0xE6283A),
+        Color::from_hex(0xFCFA00),
+        Color::from_hex(0x00E330),
+        Color::from_hex(0x0078F2),
+        Color::from_hex(0xC77AFF),
+        Color::from_hex(0xFFFFFF),
+    ];
+
+    pub fn update(&mut self, dt: Time) {
+        let dt = dt.get::<second>();
+        for particle in self.particles.iter_mut() {
+            particle.velocity.y += Self::GRAVITY * particle.gravity_scale * dt;
+            particle.position += particle.velocity * dt;
+            particle.age += dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+        self.shake = (self.shake - Self::SHAKE_DECAY_PER_SECOND * dt).max(0.);
+    }
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.shake = 0.;
+    }
+    pub fn shake_offset(&self) -> Vec2 {
+        if self.shake <= 0. {
+            return Vec2::ZERO;
+        }
+        vec2(
+            gen_range(-self.shake, self.shake),
+            gen_range(-self.shake, self.shake),
+        )
+    }
+    // sparks spray away from the spinner where a pin lands
+    pub fn sparks(&mut self, position: Vec2, color: Color) {
+        if !self.enabled {
+            return;
+        }
+        for _ in 0..16 {
+            let angle = -FRAC_PI_2 + gen_range(-1.2, 1.2);
+            let speed = gen_range(2., 5.);
+            self.particles.push(Particle {
+                position,
+                velocity: Vec2::from_angle(angle) * speed,
+                color,
+                size: gen_range(0.05, 0.12),
+                gravity_scale: 0.5,
+                age: 0.,
+                lifetime: gen_range(0.2, 0.5),
+            });
+        }
+    }
+    // the offending pin breaks into shards and the screen shakes
+    pub fn shatter(&mut self, position: Vec2, color: Color) {
+        if !self.enabled {
+            return;
+        }
+        for _ in 0..24 {
+            let angle = gen_range(0., TAU);
+            let speed = gen_range(1., 6.);
+            self.particles.push(Particle {
+                position: position + vec2(0., gen_range(-1., 0.)),
+                velocity: Vec2::from_angle(angle) * speed,
+                color,
+                size: gen_range(0.08, 0.2),
+                gravity_scale: 1.,
+                age: 0.,
+                lifetime: gen_range(0.6, 1.2),
+            });
+        }
+        self.shake = 0.3;
+    }
+    pub fn confetti(&mut self, bounds: Rect, count: usize) {
+        if !self.enabled {
+            return;
+        }
+        for _ in 0..count {
+            let position = vec2(
+                gen_range(bounds.x, bounds.x + bounds.w),
+                bounds.y + bounds.h + gen_range(0., 2.),
+            );
+            self.particles.push(Particle {
+                position,
+                velocity: vec2(gen_range(-1.5, 1.5), gen_range(-3., 0.)),
+                color: Self::CONFETTI_COLORS[gen_range(0, Self::CONFETTI_COLORS.len())],
+                size: gen_range(0.1, 0.2),
+                gravity_scale: 0.15,
+                age: 0.,
+                lifetime: gen_range(2., 4.),
+            });
+        }
+    }
+    pub fn draw(&self) {
+        for particle in self.particles.iter() {
+            let fade = 1. - particle.age / particle.lifetime;
+            draw_rectangle(
+                particle.position.x - particle.size / 2.,
+                particle.position.y - particle.size / 2.,
+                particle.size,
+                particle.size,
+                Color {
+                    a: particle.color.a * fade,
+                    ..particle.color
+                },
+            );
+        }
+    }
+}
@@ -4,6 +4,8 @@ use uom::si::{
     velocity::meter_per_second,
 };
 
+use macroquad::math::{Vec2, vec2};
+
 use crate::game::{PinFlying, PinInGun};
 
 impl From<PinInGun> for PinFlying {
@@ -15,3 +17,9 @@ impl From<PinInGun> for PinFlying {
         }
     }
 }
+
+impl PinFlying {
+    pub fn position(&self) -> Vec2 {
+        vec2(0., self.vertical_position.get::<meter>())
+    }
+}
@@ -0,0 +1,22 @@
+use macroquad::logging::warn;
+
+use crate::game::{SaveData, storage};
+
+impl SaveData {
+    const STORAGE_KEY: &'static str = "save";
+
+    pub fn load() -> SaveData {
+        let Some(save_str) = storage::load(Self::STORAGE_KEY) else {
+            return SaveData::default();
+        };
+        serde_json::from_str(&save_str).unwrap_or_else(|e| {
+            warn!("Failed to parse save data, starting fresh: {e}");
+            SaveData::default()
+        })
+    }
+    pub fn save(&self) -> Result<(), String> {
+        let save_str = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize save data: {e}"))?;
+        storage::store(Self::STORAGE_KEY, &save_str)
+    }
+}
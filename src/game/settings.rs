@@ -0,0 +1,302 @@
+use crate::game::{LandPitch, Palette, Settings, SettingsItem, Theme};
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            particles: true,
+            color_glyphs: false,
+            palette: Palette::default(),
+            theme: None,
+            landing_preview: false,
+            aim_assist: false,
+            aim_assist_window: 0.,
+            master_volume: 1.,
+            sfx_volume: 1.,
+            music_volume: 0.6,
+            muted: false,
+            land_pitch: LandPitch::default(),
+            rhythm_mode: false,
+            spinner_speed: 1.,
+            pin_speed: 1.,
+            forgiving_blockers: false,
+            extra_lives: 0,
+        }
+    }
+}
+
+impl Settings {
+    // anything that makes the game easier than normal, aiming aids included
+    pub fn assists_on(&self) -> bool {
+        self.spinner_speed != 1.
+            || self.pin_speed != 1.
+            || self.forgiving_blockers
+            || self.extra_lives > 0
+            || self.aim_assist
+            || self.landing_preview
+    }
+}
+
+impl LandPitch {
+    const ALL: [LandPitch; 3] = [LandPitch::Off, LandPitch::Combo, LandPitch::Color];
+
+    fn name(&self) -> &'static str {
+        match self {
+            LandPitch::Off => "off",
+            LandPitch::Combo => "rises",
+            LandPitch::Color => "by color",
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+// a slider from min to max in fixed steps, which stops at either end unless asked to wrap around
+fn step_slider(value: f32, step: f32, min: f32, max: f32, direction: isize, wrap: bool) -> f32 {
+    let step_count = ((max - min) / step).round() as isize;
+    let step_idx = ((value - min) / step).round() as isize + direction;
+    let step_idx = if wrap {
+        step_idx.rem_euclid(step_count + 1)
+    } else {
+        step_idx.clamp(0, step_count)
+    };
+    min + step_idx as f32 * step
+}
+
+fn percent(value: f32) -> String {
+    format!("{}%", (value * 100.).round())
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 16] = [
+        SettingsItem::Particles,
+        SettingsItem::ColorGlyphs,
+        SettingsItem::Palette,
+        SettingsItem::Theme,
+        SettingsItem::LandingPreview,
+        SettingsItem::AimAssist,
+        SettingsItem::AimAssistWindow,
+        SettingsItem::MasterVolume,
+        SettingsItem::SfxVolume,
+        SettingsItem::MusicVolume,
+        SettingsItem::LandPitch,
+        SettingsItem::RhythmMode,
+        SettingsItem::SpinnerSpeed,
+        SettingsItem::PinSpeed,
+        SettingsItem::ForgivingBlockers,
+        SettingsItem::ExtraLives,
+    ];
+    const AIM_ASSIST_WINDOW_STEP: f32 = 0.1;
+    const AIM_ASSIST_WINDOW_MAX: f32 = 1.;
+    const VOLUME_STEP: f32 = 0.1;
+    const SPEED_STEP: f32 = 0.25;
+    const SPEED_MIN: f32 = 0.5;
+    const SPEED_MAX: f32 = 1.5;
+    const MAX_EXTRA_LIVES: u32 = 3;
+
+    pub fn label(&self, settings: &Settings) -> String {
+        match self {
+            SettingsItem::Particles => format!("particles: {}", on_off(settings.particles)),
+            SettingsItem::ColorGlyphs => format!("color glyphs: {}", on_off(settings.color_glyphs)),
+            SettingsItem::Palette => format!("palette: {}", settings.palette.name()),
+            SettingsItem::Theme => format!(
+                "theme: {}",
+                settings.theme.as_deref().unwrap_or("pack default")
+            ),
+            SettingsItem::LandingPreview => {
+                format!("landing preview: {}", on_off(settings.landing_preview))
+            }
+            SettingsItem::AimAssist => format!("aim assist: {}", on_off(settings.aim_assist)),
+            SettingsItem::AimAssistWindow => match settings.aim_assist_window {
+                0. => "aim assist window: always".into(),
+                window => format!("aim assist window: {window:.1}s"),
+            },
+            SettingsItem::MasterVolume => {
+                format!("master volume: {}", percent(settings.master_volume))
+            }
+            SettingsItem::SfxVolume => format!("effects volume: {}", percent(settings.sfx_volume)),
+            SettingsItem::MusicVolume => {
+                format!("music volume: {}", percent(settings.music_volume))
+            }
+            SettingsItem::LandPitch => format!("landing pitch: {}", settings.land_pitch.name()),
+            SettingsItem::RhythmMode => format!("rhythm mode: {}", on_off(settings.rhythm_mode)),
+            SettingsItem::SpinnerSpeed => {
+                format!("spinner speed: {}", percent(settings.spinner_speed))
+            }
+            SettingsItem::PinSpeed => format!("pin speed: {}", percent(settings.pin_speed)),
+            SettingsItem::ForgivingBlockers => {
+                format!(
+                    "forgiving blockers: {}",
+                    on_off(settings.forgiving_blockers)
+                )
+            }
+            SettingsItem::ExtraLives => format!("extra lives: {}", settings.extra_lives),
+        }
+    }
+    // direction is 1 or -1 to step forward or backward, with sliders stopping at either end
+    pub fn adjust(&self, settings: &mut Settings, direction: isize) {
+        self.step(settings, direction, false);
+    }
+    // a tap can only step forward, so sliders wrap back round to the start
+    pub fn cycle(&self, settings: &mut Settings) {
+        self.step(settings, 1, true);
+    }
+    // multiple choices always wrap around, and sliders only when wrap is set
+    fn step(&self, settings: &mut Settings, direction: isize, wrap: bool) {
+        match self {
+            SettingsItem::Particles => settings.particles = !settings.particles,
+            SettingsItem::ColorGlyphs => settings.color_glyphs = !settings.color_glyphs,
+            SettingsItem::Palette => {
+                let palette_idx = Palette::ALL
+                    .iter()
+                    .position(|palette| *palette == settings.palette)
+                    .unwrap_or(0);
+                let palette_count = Palette::ALL.len() as isize;
+                settings.palette = Palette::ALL
+                    [(palette_idx as isize + direction).rem_euclid(palette_count) as usize];
+            }
+            // no theme means whatever the pack asks for, followed by each built-in theme
+            SettingsItem::Theme => {
+                let theme_idx = Theme::BUILT_IN
+                    .iter()
+                    .position(|(name, _)| Some(*name) == settings.theme.as_deref())
+                    .map_or(0, |idx| idx + 1);
+                let choice_count = Theme::BUILT_IN.len() as isize + 1;
+                let theme_idx = (theme_idx as isize + direction).rem_euclid(choice_count) as usize;
+                settings.theme = theme_idx
+                    .checked_sub(1)
+                    .map(|idx| Theme::BUILT_IN[idx].0.to_string());
+            }
+            SettingsItem::LandingPreview => settings.landing_preview = !settings.landing_preview,
+            SettingsItem::AimAssist => settings.aim_assist = !settings.aim_assist,
+            // zero shows the overlay all the time
+            SettingsItem::AimAssistWindow => {
+                settings.aim_assist_window = step_slider(
+                    settings.aim_assist_window,
+                    Self::AIM_ASSIST_WINDOW_STEP,
+                    0.,
+                    Self::AIM_ASSIST_WINDOW_MAX,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::MasterVolume => {
+                settings.master_volume = step_slider(
+                    settings.master_volume,
+                    Self::VOLUME_STEP,
+                    0.,
+                    1.,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::SfxVolume => {
+                settings.sfx_volume = step_slider(
+                    settings.sfx_volume,
+                    Self::VOLUME_STEP,
+                    0.,
+                    1.,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::MusicVolume => {
+                settings.music_volume = step_slider(
+                    settings.music_volume,
+                    Self::VOLUME_STEP,
+                    0.,
+                    1.,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::LandPitch => {
+                let pitch_idx = LandPitch::ALL
+                    .iter()
+                    .position(|land_pitch| *land_pitch == settings.land_pitch)
+                    .unwrap_or(0);
+                let pitch_count = LandPitch::ALL.len() as isize;
+                settings.land_pitch = LandPitch::ALL
+                    [(pitch_idx as isize + direction).rem_euclid(pitch_count) as usize];
+            }
+            SettingsItem::RhythmMode => settings.rhythm_mode = !settings.rhythm_mode,
+            SettingsItem::SpinnerSpeed => {
+                settings.spinner_speed = step_slider(
+                    settings.spinner_speed,
+                    Self::SPEED_STEP,
+                    Self::SPEED_MIN,
+                    Self::SPEED_MAX,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::PinSpeed => {
+                settings.pin_speed = step_slider(
+                    settings.pin_speed,
+                    Self::SPEED_STEP,
+                    Self::SPEED_MIN,
+                    Self::SPEED_MAX,
+                    direction,
+                    wrap,
+                );
+            }
+            SettingsItem::ForgivingBlockers => {
+                settings.forgiving_blockers = !settings.forgiving_blockers
+            }
+            SettingsItem::ExtraLives => {
+                let lives = settings.extra_lives as isize + direction;
+                let choice_count = Self::MAX_EXTRA_LIVES as isize + 1;
+                settings.extra_lives = if wrap {
+                    lives.rem_euclid(choice_count)
+                } else {
+                    lives.clamp(0, Self::MAX_EXTRA_LIVES as isize)
+                } as u32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_slider_stops_at_either_end() {
+        assert_eq!(step_slider(0.5, 0.1, 0., 1., 1, false), 0.6);
+        assert_eq!(step_slider(0.5, 0.1, 0., 1., -1, false), 0.4);
+        assert_eq!(step_slider(1., 0.1, 0., 1., 1, false), 1.);
+        assert_eq!(step_slider(0., 0.1, 0., 1., -1, false), 0.);
+    }
+
+    #[test]
+    fn step_slider_wraps_when_asked() {
+        assert_eq!(step_slider(1., 0.1, 0., 1., 1, true), 0.);
+        assert_eq!(step_slider(0., 0.1, 0., 1., -1, true), 1.);
+    }
+
+    #[test]
+    fn step_slider_starts_at_min() {
+        assert_eq!(step_slider(1., 0.25, 0.5, 1.5, 1, false), 1.25);
+        assert_eq!(step_slider(0.5, 0.25, 0.5, 1.5, -1, false), 0.5);
+        assert_eq!(step_slider(1.5, 0.25, 0.5, 1.5, 1, true), 0.5);
+        // values off the grid snap back onto it
+        assert_eq!(step_slider(1.01, 0.25, 0.5, 1.5, 1, false), 1.25);
+    }
+
+    #[test]
+    fn volumes_stop_at_silent_and_full() {
+        let mut settings = Settings::default();
+        for _ in 0..20 {
+            SettingsItem::MasterVolume.adjust(&mut settings, -1);
+        }
+        assert_eq!(settings.master_volume, 0.);
+        SettingsItem::MasterVolume.cycle(&mut settings);
+        assert!((settings.master_volume - 0.1).abs() < 1e-6);
+        settings.sfx_volume = 1.;
+        SettingsItem::SfxVolume.adjust(&mut settings, 1);
+        assert_eq!(settings.sfx_volume, 1.);
+        SettingsItem::SfxVolume.cycle(&mut settings);
+        assert_eq!(settings.sfx_volume, 0.);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{borrow::Cow, collections::BTreeMap, io::Cursor};
+
+use lewton::inside_ogg::OggStreamReader;
+use macroquad::{
+    audio::{Sound, load_sound_from_bytes},
+    file::load_file,
+};
+
+use crate::game::{SoundData, SoundSource, Sounds};
+
+impl Sounds {
+    pub const ALL: [Sounds; 6] = [
+        Sounds::PinFire,
+        Sounds::PinLand,
+        Sounds::LoseLevel,
+        Sounds::NextLevel,
+        Sounds::WinLevel,
+        Sounds::WinGame,
+    ];
+    // a major scale over an octave and a half
+    const PITCH_STEPS: [f32; 11] = [0., 2., 4., 5., 7., 9., 11., 12., 14., 16., 17.];
+
+    fn built_in_bytes(&self) -> &'static [u8] {
+        match self {
+            Sounds::PinFire => include_bytes!("../../assets/pin-fire.ogg"),
+            Sounds::PinLand => include_bytes!("../../assets/pin-land.ogg"),
+            Sounds::LoseLevel => include_bytes!("../../assets/lose-level.ogg"),
+            Sounds::NextLevel => include_bytes!("../../assets/next-level.ogg"),
+            Sounds::WinLevel => include_bytes!("../../assets/win-level.ogg"),
+            Sounds::WinGame => include_bytes!("../../assets/win-game.ogg"),
+        }
+    }
+}
+
+impl SoundSource {
+    // a level's sounds take priority over its pack's, which take priority over the built-in ones
+    pub fn select(
+        sound: Sounds,
+        pack_sounds: &BTreeMap<Sounds, String>,
+        level_sounds: &BTreeMap<Sounds, String>,
+    ) -> SoundSource {
+        match level_sounds.get(&sound).or_else(|| pack_sounds.get(&sound)) {
+            Some(path) => SoundSource::File(path.clone()),
+            None => SoundSource::BuiltIn(sound),
+        }
+    }
+}
+
+impl SoundSource {
+    async fn bytes(&self) -> Result<Cow<'static, [u8]>, String> {
+        match self {
+            SoundSource::BuiltIn(sound) => Ok(Cow::Borrowed(sound.built_in_bytes())),
+            SoundSource::File(path) => load_file(path)
+                .await
+                .map(Cow::Owned)
+                .map_err(|e| format!("Failed to load sound {path}: {e}")),
+        }
+    }
+    fn name(&self) -> String {
+        match self {
+            SoundSource::BuiltIn(sound) => format!("built-in sound {sound:?}"),
+            SoundSource::File(path) => format!("sound {path}"),
+        }
+    }
+}
+
+// samples are interleaved 16-bit PCM
+struct Pcm {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<i16>,
+}
+
+impl Pcm {
+    // other formats can still play, just without pitched copies
+    fn decode_if_ogg(bytes: &[u8]) -> Result<Option<Pcm>, String> {
+        if !bytes.starts_with(b"OggS") {
+            return Ok(None);
+        }
+        Self::decode_ogg(bytes).map(Some)
+    }
+    fn decode_ogg(bytes: &[u8]) -> Result<Pcm, String> {
+        let mut reader = OggStreamReader::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let mut samples = Vec::new();
+        while let Some(packet) = reader.read_dec_packet_itl().map_err(|e| e.to_string())? {
+            samples.extend(packet);
+        }
+        Ok(Pcm {
+            channels: reader.ident_hdr.audio_channels.into(),
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            samples,
+        })
+    }
+    // playing the samples back faster raises the pitch, with linear interpolation between them
+    fn resample(&self, ratio: f32) -> Pcm {
+        let channels = self.channels as usize;
+        let frame_count = self.samples.len() / channels;
+        let new_frame_count = (frame_count as f32 / ratio) as usize;
+        let mut samples = Vec::with_capacity(new_frame_count * channels);
+        for frame in 0..new_frame_count {
+            let position = frame as f32 * ratio;
+            let before = position as usize;
+            let after = (before + 1).min(frame_count - 1);
+            let fraction = position - before as f32;
+            for channel in 0..channels {
+                let a = self.samples[before * channels + channel] as f32;
+                let b = self.samples[after * channels + channel] as f32;
+                samples.push((a + (b - a) * fraction) as i16);
+            }
+        }
+        Pcm { samples, ..*self }
+    }
+    fn to_wav(&self) -> Vec<u8> {
+        let data_size = (self.samples.len() * 2) as u32;
+        let block_align = self.channels * 2;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend(b"RIFF");
+        bytes.extend((36 + data_size).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(self.channels.to_le_bytes());
+        bytes.extend(self.sample_rate.to_le_bytes());
+        bytes.extend((self.sample_rate * block_align as u32).to_le_bytes());
+        bytes.extend(block_align.to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(data_size.to_le_bytes());
+        for sample in self.samples.iter() {
+            bytes.extend(sample.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+impl SoundData {
+    async fn load_source(source: &SoundSource) -> Result<Sound, String> {
+        let bytes = source.bytes().await?;
+        load_sound_from_bytes(&bytes)
+            .await
+            .map_err(|e| format!("Failed to load {}: {e}", source.name()))
+    }
+    // tries each source at most once, returning the errors from any that failed this time
+    pub async fn load<'a>(
+        &mut self,
+        sources: impl Iterator<Item = &'a SoundSource>,
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+        for source in sources {
+            if self.loaded.contains_key(source) {
+                continue;
+            }
+            let sound = match Self::load_source(source).await {
+                Ok(sound) => Some(sound),
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            };
+            self.loaded.insert(source.clone(), sound);
+        }
+        errors
+    }
+    pub fn get(&self, source: &SoundSource) -> Option<&Sound> {
+        self.loaded.get(source).and_then(Option::as_ref)
+    }
+    // macroquad can't change pitch while playing, so each step of the scale is its own copy
+    pub async fn load_pitched(&mut self, source: &SoundSource) -> Result<(), String> {
+        if self.pitched.contains_key(source) {
+            return Ok(());
+        }
+        // remember failures too so they aren't retried
+        self.pitched.insert(source.clone(), Vec::new());
+        let bytes = source.bytes().await?;
+        let Some(pcm) = Pcm::decode_if_ogg(&bytes)
+            .map_err(|e| format!("Failed to change the pitch of {}: {e}", source.name()))?
+        else {
+            return Ok(());
+        };
+        if pcm.channels == 0 || pcm.samples.is_empty() {
+            return Ok(());
+        }
+        let mut pitched = Vec::new();
+        for semitones in Sounds::PITCH_STEPS {
+            let wav = pcm.resample(2f32.powf(semitones / 12.)).to_wav();
+            let sound = load_sound_from_bytes(&wav)
+                .await
+                .map_err(|e| format!("Failed to change the pitch of {}: {e}", source.name()))?;
+            pitched.push(sound);
+        }
+        self.pitched.insert(source.clone(), pitched);
+        Ok(())
+    }
+    // steps past the top of the scale stay on the highest note
+    pub fn get_pitched(&self, source: &SoundSource, step: usize) -> Option<&Sound> {
+        let pitched = self.pitched.get(source)?;
+        pitched.get(step.min(pitched.len().checked_sub(1)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_sounds_skip_pitching_without_errors() {
+        let wav = Pcm {
+            channels: 1,
+            sample_rate: 44100,
+            samples: vec![0, 100, -100, 0],
+        }
+        .to_wav();
+        assert!(Pcm::decode_if_ogg(&wav).unwrap().is_none());
+        let ogg = Sounds::PinLand.built_in_bytes();
+        assert!(!Pcm::decode_if_ogg(ogg).unwrap().unwrap().samples.is_empty());
+    }
+}
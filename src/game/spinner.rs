@@ -1,11 +1,17 @@
+use std::ops::Range;
+
 use uom::si::{
     angle::{degree, revolution},
     angular_velocity::degree_per_second,
-    f32::{Angle, AngularVelocity, Length},
+    f32::{Angle, AngularVelocity, Length, Time},
     length::meter,
+    time::second,
 };
 
-use crate::game::{PinFlying, PinOnSpinner, Sector, Spinner, utils::normalize_angle};
+use crate::game::{
+    Collision, ColorId, Contact, PinFlying, PinOnSpinner, Prediction, Sector, Spinner,
+    utils::normalize_angle,
+};
 
 impl Default for Spinner {
     fn default() -> Self {
@@ -15,12 +21,24 @@ impl Default for Spinner {
             angular_velocity: AngularVelocity::new::<degree_per_second>(60.),
             pins: Vec::new(),
             radius: Length::new::<meter>(2.),
+            blocker_hit_width: Self::FULL_BLOCKER_WIDTH,
         }
     }
 }
 
 impl Spinner {
-    pub fn pin_sector_collision(&self, pin: &PinFlying, sector: &Sector) -> bool {
+    pub const TICK_SECONDS: f32 = 1. / 240.;
+    const MAX_PREDICTION_SECONDS: f32 = 10.;
+    pub const FULL_BLOCKER_WIDTH: f32 = 1.;
+    pub const FORGIVING_BLOCKER_WIDTH: f32 = 0.5;
+    const EDGE_NUDGE_REVOLUTIONS: f32 = 1e-4;
+
+    // serde defaults have to be functions
+    pub fn full_blocker_width() -> f32 {
+        Self::FULL_BLOCKER_WIDTH
+    }
+
+    fn pin_sector_collision(&self, pin: &PinFlying, sector: &Sector) -> bool {
         let inside_sector_radius = pin.vertical_position.abs() < self.radius;
         let sector_angle_start_absolute =
             normalize_angle(&(self.angular_position + sector.angle_start));
@@ -31,18 +49,188 @@ impl Spinner {
             .contains(&0.75);
         inside_sector_radius && sector_is_facing_down
     }
-    pub fn pin_pin_collision(&self, flying_pin: &PinFlying, spinner_pin: &PinOnSpinner) -> bool {
+    fn pin_pin_collision(&self, flying_pin: &PinFlying, spinner_pin: &PinOnSpinner) -> bool {
         let inside_pin_radius =
             flying_pin.vertical_position.abs() < self.radius + spinner_pin.length;
-        let pin_angle_start = normalize_angle(
-            &(self.angular_position + spinner_pin.angular_position - spinner_pin.width / 2.),
-        );
-        let pin_angle_stop = pin_angle_start + spinner_pin.width;
+        let width = spinner_pin.width * self.blocker_hit_width;
+        let pin_angle_start =
+            normalize_angle(&(self.angular_position + spinner_pin.angular_position - width / 2.));
+        let pin_angle_stop = pin_angle_start + width;
         let spinner_pin_facing_down = (pin_angle_start.get::<revolution>()
             ..pin_angle_stop.get::<revolution>())
             .contains(&0.75);
         inside_pin_radius && spinner_pin_facing_down
     }
+    fn sector_color_at(&self, angle: Angle) -> Option<ColorId> {
+        self.sectors
+            .iter()
+            .find(|sector| {
+                normalize_angle(&(angle - sector.angle_start))
+                    < sector.angle_stop - sector.angle_start
+            })
+            .map(|sector| sector.color)
+    }
+    // whether the color changes across the start and stop edges of a sector
+    pub fn color_changes_at_edges(&self, sector: &Sector) -> [bool; 2] {
+        let nudge = Angle::new::<revolution>(Self::EDGE_NUDGE_REVOLUTIONS);
+        [
+            self.sector_color_at(sector.angle_start - nudge) != Some(sector.color),
+            self.sector_color_at(sector.angle_stop + nudge) != Some(sector.color),
+        ]
+    }
+    // angular distance from where a pin lands to the nearest color change or spinner pin
+    pub fn landing_margin(&self) -> Angle {
+        let landing_angle =
+            normalize_angle(&(Angle::new::<revolution>(0.75) - self.angular_position));
+        // edges shared with a same-colored neighbor can't turn a landing into a miss
+        let sector_edges = self.sectors.iter().flat_map(|sector| {
+            let [start_changes, stop_changes] = self.color_changes_at_edges(sector);
+            let start = start_changes.then_some(sector.angle_start);
+            let stop = stop_changes.then_some(sector.angle_stop);
+            start.into_iter().chain(stop)
+        });
+        let pin_edges = self.pins.iter().flat_map(|pin| {
+            [
+                pin.angular_position - pin.width / 2.,
+                pin.angular_position + pin.width / 2.,
+            ]
+        });
+        sector_edges
+            .chain(pin_edges)
+            .map(|edge| {
+                let difference = normalize_angle(&(edge - landing_angle));
+                difference.min(Angle::new::<revolution>(1.) - difference)
+            })
+            .fold(Angle::new::<revolution>(0.5), Angle::min)
+    }
+    // blockers are checked first, since they stick out past the rim, and any wrong sector breaks
+    // the pin even where sectors overlap
+    pub fn collide(&self, pin: &PinFlying) -> Option<(Collision, Range<Angle>)> {
+        if let Some(spinner_pin) = self
+            .pins
+            .iter()
+            .find(|spinner_pin| self.pin_pin_collision(pin, spinner_pin))
+        {
+            let arc = spinner_pin.angular_position - spinner_pin.width / 2.
+                ..spinner_pin.angular_position + spinner_pin.width / 2.;
+            return Some((Collision::Blocked, arc));
+        }
+        let mut collision = None;
+        for (sector_idx, sector) in self.sectors.iter().enumerate() {
+            if self.pin_sector_collision(pin, sector) {
+                let arc = sector.angle_start..sector.angle_stop;
+                if pin.color != sector.color {
+                    return Some((Collision::WrongSector, arc));
+                }
+                let margin = self.landing_margin();
+                let landed = Collision::Landed {
+                    sector: sector_idx,
+                    margin,
+                };
+                collision = Some((landed, arc));
+            }
+        }
+        collision
+    }
+    // advances the spinner and flying pins by one fixed step, moving landed pins onto the spinner
+    // and dropping any that broke
+    pub fn tick(&mut self, flying_pins: &mut Vec<PinFlying>) -> Vec<Contact> {
+        let dt = Time::new::<second>(Self::TICK_SECONDS);
+        let d_theta: Angle = (self.angular_velocity * dt).into();
+        self.angular_position = normalize_angle(&(self.angular_position + d_theta));
+        for flying_pin in flying_pins.iter_mut() {
+            flying_pin.vertical_position += flying_pin.vertical_velocity * dt;
+        }
+        let contacts: Vec<Contact> = flying_pins
+            .iter()
+            .enumerate()
+            .filter_map(|(pin_idx, pin)| {
+                self.collide(pin).map(|(collision, arc)| Contact {
+                    pin_idx,
+                    pin: pin.clone(),
+                    collision,
+                    arc,
+                })
+            })
+            .collect();
+        for contact in contacts.iter().rev() {
+            let pin = flying_pins.remove(contact.pin_idx);
+            if let Collision::Landed { .. } = contact.collision {
+                self.take_pin(pin);
+            }
+        }
+        contacts
+    }
+    // plays the next pin forward with the same steps as the game, so the answer always matches
+    pub fn predict(&self, flying_pins: &[PinFlying], next_pin: PinFlying) -> Option<Prediction> {
+        let mut spinner = self.clone();
+        let mut flying_pins = flying_pins.to_vec();
+        flying_pins.push(next_pin);
+        let max_ticks = (Self::MAX_PREDICTION_SECONDS / Self::TICK_SECONDS) as usize;
+        for _ in 0..max_ticks {
+            // the next pin stays last as the pins ahead of it land
+            let next_pin_idx = flying_pins.len() - 1;
+            for contact in spinner.tick(&mut flying_pins) {
+                if contact.pin_idx == next_pin_idx {
+                    return Some(Prediction {
+                        collision: contact.collision,
+                        angle: Angle::new::<revolution>(0.75) - spinner.angular_position,
+                        arc: contact.arc,
+                    });
+                }
+                // an earlier pin ends the level first
+                if !matches!(contact.collision, Collision::Landed { .. }) {
+                    return None;
+                }
+            }
+        }
+        None
+    }
+    // whether firing within the given number of steps, checked every sample_ticks, lands the next
+    // pin, found by stepping one copy forward and predicting a shot off it at each sample
+    pub fn safe_shot_within(
+        &self,
+        flying_pins: &[PinFlying],
+        next_pin: &PinFlying,
+        max_delay_ticks: usize,
+        sample_ticks: usize,
+    ) -> bool {
+        let mut spinner = self.clone();
+        let mut flying_pins = flying_pins.to_vec();
+        for delay_ticks in 0..=max_delay_ticks {
+            if delay_ticks % sample_ticks.max(1) == 0
+                && spinner
+                    .predict(&flying_pins, next_pin.clone())
+                    .is_some_and(|prediction| {
+                        matches!(prediction.collision, Collision::Landed { .. })
+                    })
+            {
+                return true;
+            }
+            // a pin already in flight breaking ends the level before any shot
+            let contacts = spinner.tick(&mut flying_pins);
+            if contacts
+                .iter()
+                .any(|contact| !matches!(contact.collision, Collision::Landed { .. }))
+            {
+                return false;
+            }
+        }
+        false
+    }
+    // the nearest speed that turns once in a whole number of beats, in the same direction
+    pub fn beat_synced_velocity(velocity: AngularVelocity, bpm: f32) -> AngularVelocity {
+        let degrees_per_second = velocity.get::<degree_per_second>();
+        if degrees_per_second == 0. {
+            return velocity;
+        }
+        let seconds_per_turn = 360. / degrees_per_second.abs();
+        let seconds_per_beat = 60. / bpm;
+        let beats_per_turn = (seconds_per_turn / seconds_per_beat).round().max(1.);
+        AngularVelocity::new::<degree_per_second>(
+            360. / (beats_per_turn * seconds_per_beat) * degrees_per_second.signum(),
+        )
+    }
     pub fn take_pin(&mut self, pin: PinFlying) {
         self.pins.push(PinOnSpinner {
             color: pin.color,
@@ -52,3 +240,121 @@ impl Spinner {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_utils::{
+        blocker, fired, mixed_spinner, sector, spinner_landing_at, touching,
+    };
+
+    fn collision(spinner: &Spinner, pin: &PinFlying) -> Option<Collision> {
+        spinner.collide(pin).map(|(collision, _)| collision)
+    }
+    fn margin(spinner: &Spinner) -> f32 {
+        spinner.landing_margin().get::<revolution>()
+    }
+
+    #[test]
+    fn landing_margin_ignores_full_circle_seam() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.01);
+        assert!((margin(&spinner) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn landing_margin_ignores_same_color_neighbors() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 0.5), sector(0, 0.5, 1.)], 0.48);
+        assert!((margin(&spinner) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn landing_margin_counts_color_changes_and_pins() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 0.5), sector(1, 0.5, 1.)], 0.48);
+        assert!((margin(&spinner) - 0.02).abs() < 1e-4);
+        let mut spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.2);
+        spinner.pins.push(blocker(0.3, 0.02));
+        assert!((margin(&spinner) - 0.09).abs() < 1e-4);
+    }
+
+    #[test]
+    fn collide_lands_on_a_matching_sector() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 0.5), sector(1, 0.5, 1.)], 0.25);
+        assert!(matches!(
+            collision(&spinner, &touching(0)),
+            Some(Collision::Landed { .. })
+        ));
+        assert_eq!(
+            collision(&spinner, &touching(1)),
+            Some(Collision::WrongSector)
+        );
+        assert_eq!(collision(&spinner, &fired(0)), None);
+    }
+
+    #[test]
+    fn collide_checks_blockers_before_sectors() {
+        let mut spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.25);
+        spinner.pins.push(blocker(0.25, 0.05));
+        assert_eq!(collision(&spinner, &touching(0)), Some(Collision::Blocked));
+    }
+
+    #[test]
+    fn collide_breaks_on_any_wrong_overlapping_sector() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 0.5), sector(1, 0.2, 0.3)], 0.25);
+        assert_eq!(
+            collision(&spinner, &touching(0)),
+            Some(Collision::WrongSector)
+        );
+        let spinner = spinner_landing_at(vec![sector(1, 0.2, 0.3), sector(0, 0., 0.5)], 0.25);
+        assert_eq!(
+            collision(&spinner, &touching(0)),
+            Some(Collision::WrongSector)
+        );
+    }
+
+    #[test]
+    fn predict_matches_playing_the_shot() {
+        for step in 0..200 {
+            for color in [0, 1] {
+                let spinner = mixed_spinner(step as f32 / 200.);
+                let prediction = spinner.predict(&[], fired(color)).expect("the pin hits");
+                let mut played = spinner.clone();
+                let mut flying_pins = vec![fired(color)];
+                let contact = loop {
+                    if let Some(contact) = played.tick(&mut flying_pins).pop() {
+                        break contact;
+                    }
+                };
+                assert_eq!(prediction.collision, contact.collision);
+                assert_eq!(prediction.arc, contact.arc);
+                let played_angle = Angle::new::<revolution>(0.75) - played.angular_position;
+                assert_eq!(prediction.angle, played_angle);
+            }
+        }
+    }
+
+    #[test]
+    fn predict_follows_pins_already_in_flight() {
+        let spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.5);
+        let mut ahead = fired(0);
+        ahead.vertical_position = Length::new::<meter>(-2.5);
+        let prediction = spinner.predict(&[ahead], fired(0)).expect("the pin hits");
+        assert!(matches!(prediction.collision, Collision::Landed { .. }));
+    }
+
+    #[test]
+    fn predict_gives_up_when_an_earlier_pin_breaks() {
+        let spinner = mixed_spinner(0.1);
+        let mut breaking = fired(1);
+        breaking.vertical_position = Length::new::<meter>(-2.1);
+        assert!(spinner.predict(&[breaking], fired(0)).is_none());
+    }
+
+    #[test]
+    fn safe_shot_within_waits_for_a_matching_sector() {
+        // the matching sector comes round a quarter turn later, at 60 degrees a second
+        let spinner = spinner_landing_at(vec![sector(1, 0., 0.5), sector(0, 0.5, 1.)], 0.3);
+        let ticks_per_second = (1. / Spinner::TICK_SECONDS) as usize;
+        assert!(!spinner.safe_shot_within(&[], &fired(0), ticks_per_second, 4));
+        assert!(spinner.safe_shot_within(&[], &fired(0), 3 * ticks_per_second, 4));
+    }
+}
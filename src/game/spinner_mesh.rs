@@ -0,0 +1,305 @@
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+use uom::si::{
+    angle::radian,
+    f32::{Angle, Length},
+    length::meter,
+};
+
+use crate::game::{ColorMap, MeshBatch, Spinner, SpinnerMesh};
+
+impl MeshBatch {
+    // macroquad clamps any geometry that reaches its default draw call size of 10000 vertices and
+    // 5000 indices, so batches stay under both and their indices always fit in a u16
+    const MAX_VERTICES: usize = 8192;
+    const MAX_INDICES: usize = 4096;
+}
+
+impl SpinnerMesh {
+    const PIXELS_PER_SEGMENT: f32 = 4.;
+    const SEGMENT_STEP: u32 = 16;
+    const MIN_SEGMENTS_PER_TURN: u32 = 32;
+    const MAX_SEGMENTS_PER_TURN: u32 = 512;
+    const PIN_WIDTH_METERS: f32 = 0.2;
+
+    // enough segments that each one spans a few pixels, rounded so small size changes reuse the
+    // mesh
+    fn segments_per_turn(radius: Length, pixels_per_meter: f32) -> u32 {
+        let circumference_pixels = TAU * radius.get::<meter>() * pixels_per_meter;
+        let segments = (circumference_pixels / Self::PIXELS_PER_SEGMENT) as u32;
+        segments
+            .next_multiple_of(Self::SEGMENT_STEP)
+            .clamp(Self::MIN_SEGMENTS_PER_TURN, Self::MAX_SEGMENTS_PER_TURN)
+    }
+    fn geometry_key(
+        spinner: &Spinner,
+        color_map: &ColorMap,
+        segments_per_turn: u32,
+        feather: f32,
+    ) -> Vec<u32> {
+        let mut key = vec![
+            segments_per_turn,
+            feather.to_bits(),
+            spinner.radius.get::<meter>().to_bits(),
+        ];
+        for sector in spinner.sectors.iter() {
+            key.extend([
+                sector.angle_start.get::<radian>().to_bits(),
+                sector.angle_stop.get::<radian>().to_bits(),
+            ]);
+            key.extend(
+                color_map
+                    .display(sector.color)
+                    .to_vec()
+                    .to_array()
+                    .map(f32::to_bits),
+            );
+        }
+        for pin in spinner.pins.iter() {
+            key.extend([
+                pin.angular_position.get::<radian>().to_bits(),
+                pin.length.get::<meter>().to_bits(),
+            ]);
+            key.extend(
+                color_map
+                    .display(pin.color)
+                    .to_vec()
+                    .to_array()
+                    .map(f32::to_bits),
+            );
+        }
+        key
+    }
+    // the face texture covers the whole disk, with its top pointing along the spinner's zero angle
+    fn face_uv(direction: Vec2) -> Vec2 {
+        let uv_direction = direction.rotate(Vec2::Y);
+        vec2(0.5 + 0.5 * uv_direction.x, 0.5 - 0.5 * uv_direction.y)
+    }
+    fn vertex(position: Vec2, uv: Vec2, color: Color) -> Vertex {
+        Vertex::new(position.x, position.y, 0., uv.x, uv.y, color)
+    }
+    // a convex polygon from corners in order around its perimeter, in whichever batch has room
+    fn push_polygon(batches: &mut Vec<MeshBatch>, corners: &[Vertex]) {
+        let index_count = 3 * (corners.len() - 2);
+        let fits = batches.last().is_some_and(|batch| {
+            batch.vertices.len() + corners.len() <= MeshBatch::MAX_VERTICES
+                && batch.indices.len() + index_count <= MeshBatch::MAX_INDICES
+        });
+        if !fits {
+            batches.push(MeshBatch::default());
+        }
+        let batch = batches
+            .last_mut()
+            .expect("there is always a batch with room");
+        let first_idx = batch.vertices.len() as u16;
+        batch.vertices.extend_from_slice(corners);
+        for corner_idx in 1..corners.len() as u16 - 1 {
+            batch.indices.extend_from_slice(&[
+                first_idx,
+                first_idx + corner_idx,
+                first_idx + corner_idx + 1,
+            ]);
+        }
+    }
+    // builds the spinner in its own frame, so rotation and scale only change the model matrix
+    fn rebuild(
+        &mut self,
+        spinner: &Spinner,
+        color_map: &ColorMap,
+        segments_per_turn: u32,
+        feather: f32,
+    ) {
+        self.face_batches.clear();
+        self.pin_batches.clear();
+        let radius = spinner.radius.get::<meter>();
+        if radius <= 0. {
+            return;
+        }
+
+        // sectors as triangle fans, with a transparent ring outside the rim to soften the edge
+        for sector in spinner.sectors.iter() {
+            let start = sector.angle_start.get::<radian>();
+            let arc = (sector.angle_stop - sector.angle_start).get::<radian>();
+            if arc <= 0. {
+                continue;
+            }
+            let n = ((arc / TAU * segments_per_turn as f32).ceil() as u32).max(1);
+            let color = color_map.display(sector.color);
+            let transparent = Color { a: 0., ..color };
+            let center = Self::vertex(Vec2::ZERO, vec2(0.5, 0.5), color);
+            let rim = |i: u32| {
+                let direction = Vec2::from_angle(start + arc * i as f32 / n as f32);
+                let uv = Self::face_uv(direction);
+                (
+                    Self::vertex(direction * radius, uv, color),
+                    Self::vertex(direction * (radius + feather), uv, transparent),
+                )
+            };
+            for i in 0..n {
+                let (previous_rim, previous_feather) = rim(i);
+                let (next_rim, next_feather) = rim(i + 1);
+                Self::push_polygon(&mut self.face_batches, &[center, previous_rim, next_rim]);
+                Self::push_polygon(
+                    &mut self.face_batches,
+                    &[previous_rim, previous_feather, next_feather, next_rim],
+                );
+            }
+        }
+
+        // straight edges where the color changes fade out over the neighbor, once every sector is
+        // down so that no neighbor gets drawn over them
+        for sector in spinner.sectors.iter() {
+            let color = color_map.display(sector.color);
+            let transparent = Color { a: 0., ..color };
+            let [start_changes, stop_changes] = spinner.color_changes_at_edges(sector);
+            for (angle, outward_sign, changes) in [
+                (sector.angle_start, -1., start_changes),
+                (sector.angle_stop, 1., stop_changes),
+            ] {
+                if !changes || sector.angle_stop <= sector.angle_start {
+                    continue;
+                }
+                let direction = Vec2::from_angle(angle.get::<radian>());
+                let outward = direction.perp() * outward_sign * feather;
+                let rim_uv = Self::face_uv(direction);
+                Self::push_polygon(
+                    &mut self.face_batches,
+                    &[
+                        Self::vertex(Vec2::ZERO, vec2(0.5, 0.5), color),
+                        Self::vertex(direction * radius, rim_uv, color),
+                        Self::vertex(direction * radius + outward, rim_uv, transparent),
+                        Self::vertex(outward, vec2(0.5, 0.5), transparent),
+                    ],
+                );
+            }
+        }
+
+        // pins as rectangles pointing out from the rim, each with a transparent border
+        for pin in spinner.pins.iter() {
+            let direction = Vec2::from_angle(pin.angular_position.get::<radian>());
+            let tangent = direction.perp();
+            let base = direction * radius;
+            let tip = direction * (radius + pin.length.get::<meter>());
+            let half_width = Self::PIN_WIDTH_METERS / 2.;
+            let color = color_map.display(pin.color);
+            let transparent = Color { a: 0., ..color };
+            let corners = [
+                base - tangent * half_width,
+                tip - tangent * half_width,
+                tip + tangent * half_width,
+                base + tangent * half_width,
+            ];
+            let outer_corners = [
+                corners[0] - (tangent + direction) * feather,
+                corners[1] - (tangent - direction) * feather,
+                corners[2] + (tangent + direction) * feather,
+                corners[3] + (tangent - direction) * feather,
+            ];
+            // sprites run from the rim at the top of the texture to the tip at the bottom
+            let uvs = [vec2(0., 0.), vec2(0., 1.), vec2(1., 1.), vec2(1., 0.)];
+            let inner = [0, 1, 2, 3].map(|i| Self::vertex(corners[i], uvs[i], color));
+            let outer = [0, 1, 2, 3].map(|i| Self::vertex(outer_corners[i], uvs[i], transparent));
+            Self::push_polygon(&mut self.pin_batches, &inner);
+            for side in 0..4 {
+                let next = (side + 1) % 4;
+                Self::push_polygon(
+                    &mut self.pin_batches,
+                    &[inner[side], outer[side], outer[next], inner[next]],
+                );
+            }
+        }
+    }
+    pub fn draw(
+        &mut self,
+        gl: &mut InternalGlContext,
+        spinner: &Spinner,
+        color_map: &ColorMap,
+        pixels_per_meter: f32,
+        scale: f32,
+        rotation: Angle,
+    ) {
+        let segments_per_turn = Self::segments_per_turn(spinner.radius, pixels_per_meter);
+        let feather = 1. / pixels_per_meter;
+        let key = Self::geometry_key(spinner, color_map, segments_per_turn, feather);
+        if key != self.key {
+            self.rebuild(spinner, color_map, segments_per_turn, feather);
+            self.key = key;
+        }
+        gl.quad_gl
+            .push_model_matrix(Mat4::from_scale_rotation_translation(
+                vec3(scale, scale, 1.),
+                Quat::from_rotation_z(rotation.get::<radian>()),
+                Vec3::ZERO,
+            ));
+        // textures tint by the vertex color, so the face and pins are drawn as separate batches
+        gl.quad_gl.draw_mode(DrawMode::Triangles);
+        gl.quad_gl.texture(self.face_texture.as_ref());
+        for batch in self.face_batches.iter() {
+            gl.quad_gl.geometry(&batch.vertices, &batch.indices);
+        }
+        gl.quad_gl.texture(self.pin_texture.as_ref());
+        for batch in self.pin_batches.iter() {
+            gl.quad_gl.geometry(&batch.vertices, &batch.indices);
+        }
+        gl.quad_gl.texture(None);
+        gl.quad_gl.pop_model_matrix();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_utils::{blocker, sector, spinner_landing_at};
+
+    fn face_vertex_count(mesh: &SpinnerMesh) -> usize {
+        mesh.face_batches
+            .iter()
+            .map(|batch| batch.vertices.len())
+            .sum()
+    }
+
+    #[test]
+    fn batches_fit_a_draw_call() {
+        let mut spinner = spinner_landing_at(vec![sector(0, 0., 1.)], 0.);
+        spinner.pins = (0..2000).map(|i| blocker(i as f32 / 2000., 0.)).collect();
+        let mut mesh = SpinnerMesh::default();
+        let segments = SpinnerMesh::MAX_SEGMENTS_PER_TURN;
+        mesh.rebuild(&spinner, &ColorMap::default(), segments, 0.01);
+        assert!(mesh.pin_batches.len() > 1);
+        for batch in mesh.face_batches.iter().chain(mesh.pin_batches.iter()) {
+            assert!(batch.vertices.len() <= MeshBatch::MAX_VERTICES);
+            assert!(batch.indices.len() <= MeshBatch::MAX_INDICES);
+            assert!(
+                batch
+                    .indices
+                    .iter()
+                    .all(|&idx| (idx as usize) < batch.vertices.len())
+            );
+        }
+    }
+
+    #[test]
+    fn only_color_changes_get_feathered_edges() {
+        let feathered_edges = |colors: [u8; 3]| {
+            let sectors = vec![
+                sector(colors[0], 0., 0.3),
+                sector(colors[1], 0.3, 0.7),
+                sector(colors[2], 0.7, 1.),
+            ];
+            let mut mesh = SpinnerMesh::default();
+            mesh.rebuild(
+                &spinner_landing_at(sectors, 0.),
+                &ColorMap::default(),
+                64,
+                0.01,
+            );
+            face_vertex_count(&mesh)
+        };
+        // each feathered edge adds a quad, and the seam at zero stays hidden between the 0 sectors
+        assert_eq!(
+            feathered_edges([0, 1, 0]),
+            feathered_edges([0, 0, 0]) + 4 * 4
+        );
+    }
+}
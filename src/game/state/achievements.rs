@@ -0,0 +1,202 @@
+use macroquad::{color::colors, prelude::*};
+use uom::si::f32::Time;
+
+use crate::game::{Achievement, Anchor, GameEvent, GameState, LevelStats, Toast, Ui};
+
+impl<'a> GameState<'a> {
+    const TOAST_BACKGROUND: Color = Color::new(0., 0., 0., 0.7);
+    const LOCKED_TEXT: Color = Color::new(1., 1., 1., 0.45);
+
+    fn unlock(&mut self, achievement: Achievement) {
+        if self.save_data.achievements.insert(achievement) {
+            self.toasts.push_back(Toast::new(achievement));
+        }
+    }
+    // practice can undo shots, so nothing done there counts toward achievements
+    pub(super) fn unlock_achievements(&mut self, event: &GameEvent) {
+        if !self.game_mode.is_ranked() {
+            return;
+        }
+        let unlocked_count = self.save_data.achievements.len();
+        match event {
+            GameEvent::PinLanded { margin, .. } => {
+                self.save_data.pins_landed += 1;
+                if LevelStats::is_close_call(*margin) {
+                    self.clean_attempt = false;
+                }
+                if self.save_data.pins_landed >= Achievement::CENTURION_PINS {
+                    self.unlock(Achievement::Centurion);
+                }
+            }
+            GameEvent::LevelWon | GameEvent::GameWon => {
+                if self.clean_attempt {
+                    self.unlock(Achievement::SteadyHand);
+                }
+                if self.levels[self.level_idx].pins_in_gun.len() > Achievement::LOADED_PINS {
+                    self.unlock(Achievement::Loaded);
+                }
+                if let GameEvent::GameWon = event
+                    && self.built_in_pack
+                {
+                    self.unlock(Achievement::FullCircle);
+                }
+            }
+            // a broken pin spoils the attempt even when a spare life saves it
+            GameEvent::LifeLost => self.clean_attempt = false,
+            GameEvent::PinFired { .. }
+            | GameEvent::PinBlocked { .. }
+            | GameEvent::LevelStarted { .. }
+            | GameEvent::LevelLost => return,
+        }
+        // landings are only counted on disk at the end of each attempt, or when something unlocks
+        let attempt_over = matches!(event, GameEvent::LevelWon | GameEvent::GameWon);
+        let unlocked = self.save_data.achievements.len() > unlocked_count;
+        self.save_after_event |= attempt_over || unlocked;
+    }
+    // only the oldest toast is shown, so it's the only one that ages
+    pub(super) fn update_toasts(&mut self, dt: Time) {
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.age += dt;
+            if toast.expired() {
+                self.toasts.pop_front();
+            }
+        }
+    }
+    fn achievements_close_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(0., -90.), vec2(160., 44.))
+    }
+    fn achievement_rect(&self, achievement_idx: usize) -> Rect {
+        self.ui.rect(
+            Anchor::Top,
+            vec2(0., 150. + achievement_idx as f32 * 72.),
+            vec2(360., 64.),
+        )
+    }
+    // returns true while the achievements screen is open so the game stays paused, and on the
+    // frame it closes
+    pub(super) fn update_achievements_screen(&mut self) -> bool {
+        let was_open = self.achievements_open;
+        self.achievements_open = if was_open {
+            !(is_key_pressed(KeyCode::Escape)
+                || is_key_pressed(KeyCode::A)
+                || Ui::clicked(self.achievements_close_rect()))
+        } else {
+            self.settings_menu.is_none() && is_key_pressed(KeyCode::A)
+        };
+        was_open || self.achievements_open
+    }
+    pub(crate) fn draw_toast(&self) {
+        let Some(toast) = self.toasts.front() else {
+            return;
+        };
+        let opacity = toast.opacity();
+        let panel = self
+            .ui
+            .rect(Anchor::TopLeft, vec2(12., 52.), vec2(240., 48.));
+        let background = Color {
+            a: Self::TOAST_BACKGROUND.a * opacity,
+            ..Self::TOAST_BACKGROUND
+        };
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, background);
+        let params = TextParams {
+            color: Color {
+                a: opacity,
+                ..colors::WHITE
+            },
+            ..self.text_params.clone()
+        };
+        let center_x = panel.center().x;
+        let label_y = panel.y + self.ui.px(14.);
+        self.ui.text_at(
+            "achievement unlocked",
+            vec2(center_x, label_y),
+            12.,
+            &params,
+        );
+        let name_y = panel.y + self.ui.px(32.);
+        self.ui.text_at(
+            toast.achievement.name(),
+            vec2(center_x, name_y),
+            18.,
+            &TextParams {
+                color: Color {
+                    a: opacity,
+                    ..self.theme.highlight_color
+                },
+                ..params.clone()
+            },
+        );
+    }
+    pub(crate) fn draw_achievements_screen(&self) {
+        if !self.achievements_open {
+            return;
+        }
+        let screen = self.ui.screen();
+        draw_rectangle(
+            screen.x,
+            screen.y,
+            screen.w,
+            screen.h,
+            Color::new(0., 0., 0., 0.75),
+        );
+        let white_text = TextParams {
+            color: colors::WHITE,
+            ..self.text_params.clone()
+        };
+        self.ui
+            .text("achievements", Anchor::Top, vec2(0., 96.), 36., &white_text);
+        for (achievement_idx, achievement) in Achievement::ALL.iter().enumerate() {
+            let rect = self.achievement_rect(achievement_idx);
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::new(1., 1., 1., 0.12));
+            let unlocked = self.save_data.achievements.contains(achievement);
+            let (name, name_color) = if unlocked {
+                (achievement.name().to_string(), self.theme.highlight_color)
+            } else {
+                (
+                    format!("{} (locked)", achievement.name()),
+                    Self::LOCKED_TEXT,
+                )
+            };
+            let name_params = TextParams {
+                color: name_color,
+                ..white_text.clone()
+            };
+            let description_params = TextParams {
+                color: if unlocked {
+                    colors::WHITE
+                } else {
+                    Self::LOCKED_TEXT
+                },
+                ..white_text.clone()
+            };
+            let center_x = rect.center().x;
+            self.ui.text_at(
+                &name,
+                vec2(center_x, rect.y + self.ui.px(22.)),
+                20.,
+                &name_params,
+            );
+            self.ui.text_at(
+                achievement.description(),
+                vec2(center_x, rect.y + self.ui.px(46.)),
+                14.,
+                &description_params,
+            );
+        }
+        self.ui.text(
+            &format!(
+                "{} / {} unlocked, {} pins landed",
+                self.save_data.achievements.len(),
+                Achievement::ALL.len(),
+                self.save_data.pins_landed
+            ),
+            Anchor::Top,
+            vec2(0., 150. + Achievement::ALL.len() as f32 * 72. + 16.),
+            16.,
+            &white_text,
+        );
+        self.ui
+            .button(self.achievements_close_rect(), "close", false, &white_text);
+    }
+}
@@ -0,0 +1,44 @@
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{Banner, Checkpoint, GameEvent, GameState, LevelState};
+
+impl<'a> GameState<'a> {
+    pub(super) fn full_lives(&self) -> u32 {
+        self.levels[self.level_idx].lives + self.save_data.settings.extra_lives
+    }
+    // landing the pin that a checkpoint asks for saves the spinner and gun as they are
+    pub(super) fn save_checkpoint(&mut self, event: &GameEvent) {
+        if !matches!(event, GameEvent::PinLanded { .. }) {
+            return;
+        }
+        let level = &self.levels[self.level_idx];
+        let landed = self.spinner.pins.len() - level.spinner.pins.len();
+        if !level.checkpoints.contains(&landed) {
+            return;
+        }
+        self.checkpoint = Some(Checkpoint::new(
+            &self.spinner,
+            &self.pin_gun.pins,
+            &self.flying_pins,
+            self.full_lives(),
+        ));
+        self.event_banners.push(Banner::info("Checkpoint"));
+    }
+    // picks a lost level back up from its last checkpoint, if it reached one
+    pub(super) fn restore_checkpoint(&mut self) -> bool {
+        let Some(checkpoint) = &self.checkpoint else {
+            return false;
+        };
+        checkpoint.restore(
+            &mut self.spinner,
+            &mut self.pin_gun.pins,
+            &mut self.flying_pins,
+            &mut self.lives,
+        );
+        self.particles.clear();
+        self.combo = 0;
+        self.physics_lag = Time::new::<second>(0.);
+        self.level_state = LevelState::Playing;
+        true
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::btree_map::Entry;
+
+use crate::game::{Banner, DailyResult, GameEvent, GameState, LevelStats};
+
+impl<'a> GameState<'a> {
+    // keeps the best finished run for each day, so past dailies can be compared later
+    pub(super) fn record_daily_result(&mut self, event: &GameEvent) {
+        let Some(date) = self.daily_date else {
+            return;
+        };
+        if !matches!(event, GameEvent::GameWon) || self.pack_name != date.pack_name() {
+            return;
+        }
+        let total: LevelStats = self.level_stats.iter().copied().sum();
+        let result = DailyResult {
+            score: total.score(),
+            time: self.run_time(),
+            runs: 1,
+            assisted: total.assisted,
+        };
+        let new_best = match self.save_data.daily_results.entry(date.to_string()) {
+            Entry::Vacant(entry) => {
+                entry.insert(result);
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                let best = entry.get_mut();
+                let runs = best.runs + 1;
+                let new_best = result.beats(best);
+                if new_best {
+                    *best = result;
+                }
+                best.runs = runs;
+                new_best
+            }
+        };
+        self.save_after_event = true;
+        self.event_banners.push(Banner::info(if new_best {
+            format!("New best for {date}!")
+        } else {
+            format!("Daily run saved for {date}")
+        }));
+    }
+}
@@ -0,0 +1,108 @@
+use std::mem;
+
+use macroquad::prelude::*;
+use uom::si::length::meter;
+
+use crate::game::{
+    Banner, ColorId, EventHandler, GameEvent, GameState, LandPitch, LevelStats, Sounds,
+};
+
+impl<'a> GameState<'a> {
+    pub(super) fn subscribe(&mut self, handler: EventHandler<'a>) {
+        self.event_handlers.push(handler);
+    }
+    // handlers run straight away rather than from a queue, so they all see the state the event
+    // came from, and the save and banner wait until every handler is done
+    pub(super) fn emit(&mut self, event: GameEvent) {
+        for handler_idx in 0..self.event_handlers.len() {
+            let handler = self.event_handlers[handler_idx];
+            handler(self, &event);
+        }
+        if mem::take(&mut self.save_after_event)
+            && let Err(e) = self.save_data.save()
+        {
+            self.event_banners.push(Banner::error(e));
+        }
+        if let Some(banner) = Banner::combine(mem::take(&mut self.event_banners)) {
+            self.banner = Some(banner);
+        }
+    }
+    fn play_sound(&mut self, sound: Sounds) {
+        self.mixer.play(sound, &self.save_data.settings);
+    }
+    // each landing climbs a step up the scale, or takes the step that matches the pin's color
+    fn play_land_sound(&mut self, color: ColorId, combo: usize) {
+        let settings = &self.save_data.settings;
+        match settings.land_pitch {
+            LandPitch::Off => self.mixer.play(Sounds::PinLand, settings),
+            LandPitch::Combo => self.mixer.play_pitched(Sounds::PinLand, combo, settings),
+            LandPitch::Color => {
+                self.mixer
+                    .play_pitched(Sounds::PinLand, color.0 as usize, settings)
+            }
+        }
+    }
+    pub(super) fn play_event_sound(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::PinFired { .. } => self.play_sound(Sounds::PinFire),
+            // the last pin lets the win sound play on its own
+            GameEvent::PinLanded { color, combo, .. } => {
+                if !self.pin_gun.pins.is_empty() {
+                    self.play_land_sound(*color, *combo);
+                }
+            }
+            GameEvent::PinBlocked { .. } => (),
+            GameEvent::LevelStarted { .. } => self.play_sound(Sounds::NextLevel),
+            GameEvent::LevelWon => self.play_sound(Sounds::WinLevel),
+            GameEvent::LifeLost | GameEvent::LevelLost => self.play_sound(Sounds::LoseLevel),
+            GameEvent::GameWon => self.play_sound(Sounds::WinGame),
+        }
+    }
+    pub(super) fn spawn_event_particles(&mut self, event: &GameEvent) {
+        let confetti_bounds = Rect::new(-5., 5., 10., 0.);
+        match event {
+            GameEvent::PinFired { on_beat: true } => {
+                let muzzle = vec2(0., -5.);
+                self.particles.sparks(muzzle, self.theme.highlight_color);
+            }
+            GameEvent::PinLanded { sector, .. } => {
+                let landing_point = vec2(0., -self.spinner.radius.get::<meter>());
+                let color = self.color_map.display(self.spinner.sectors[*sector].color);
+                self.particles.sparks(landing_point, color);
+            }
+            GameEvent::PinBlocked { pin } => {
+                let color = self.color_map.display(pin.color);
+                self.particles.shatter(pin.position(), color);
+            }
+            GameEvent::LevelWon => self.particles.confetti(confetti_bounds, 60),
+            GameEvent::GameWon => self.particles.confetti(confetti_bounds, 200),
+            GameEvent::PinFired { on_beat: false }
+            | GameEvent::LevelStarted { .. }
+            | GameEvent::LifeLost
+            | GameEvent::LevelLost => (),
+        }
+    }
+    pub(super) fn record_event_stats(&mut self, event: &GameEvent) {
+        let stats = &mut self.level_stats[self.level_idx];
+        match event {
+            GameEvent::PinFired { on_beat } => {
+                stats.pins_fired += 1;
+                if *on_beat {
+                    stats.on_beat += 1;
+                }
+            }
+            GameEvent::PinLanded { margin, .. } => {
+                stats.pins_landed += 1;
+                if LevelStats::is_close_call(*margin) {
+                    stats.close_calls += 1;
+                }
+            }
+            GameEvent::LevelStarted { retry: true } => stats.retries += 1,
+            GameEvent::LevelWon | GameEvent::GameWon => self.record_split(),
+            GameEvent::PinBlocked { .. }
+            | GameEvent::LevelStarted { retry: false }
+            | GameEvent::LifeLost
+            | GameEvent::LevelLost => (),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use macroquad::{color::colors, prelude::*};
+use uom::si::time::second;
+
+use crate::game::{
+    Anchor, GameMode, GameState, LevelState, LevelStats, PinInGun,
+    utils::{draw_text_ex_center, format_time, use_white_text, wrap_text},
+};
+
+impl<'a> GameState<'a> {
+    const BANNER_RED: Color = Color::from_hex(0xB3261E);
+    const BANNER_GRAY: Color = Color::from_hex(0x3C3C3C);
+    const SPLIT_AHEAD: Color = Color::from_hex(0x1FAA59);
+    const SPLIT_BEHIND: Color = Color::from_hex(0xD93A3A);
+    const OVERLAY: Color = Color::new(0., 0., 0., 0.6);
+    const GUN_QUEUE_LENGTH: f32 = 4.1;
+
+    // the gun refills one pin at a time between levels
+    fn loaded_pins(&self) -> usize {
+        self.transition
+            .as_ref()
+            .map_or(self.pin_gun.pins.len(), |transition| {
+                transition.loaded_pins()
+            })
+    }
+    // every pin in the gun from the one about to fire downward, squeezed together when there are
+    // more than fit at full spacing
+    pub(super) fn gun_queue(&self) -> impl Iterator<Item = (usize, &PinInGun, f32)> {
+        let spacing = self.gun_pin_spacing();
+        self.pin_gun
+            .pins
+            .iter()
+            .rev()
+            .take(self.loaded_pins())
+            .enumerate()
+            .map(move |(pin_idx, pin_in_gun)| {
+                (pin_idx, pin_in_gun, -5.5 - pin_idx as f32 * spacing)
+            })
+    }
+    pub(super) fn gun_pin_spacing(&self) -> f32 {
+        match self.pin_gun.pins.len() {
+            0..=5 => 1.,
+            pin_count => Self::GUN_QUEUE_LENGTH / (pin_count - 1) as f32,
+        }
+    }
+    // numbers on the pins in the gun, placed over where the world camera drew them, with only the
+    // next pin labeled once the queue is too tight to fit them all
+    pub(crate) fn draw_gun_labels(&self, camera: &Camera2D) {
+        let loaded_pins = self.loaded_pins();
+        let label_all = self.gun_pin_spacing() >= 0.6;
+        for (pin_idx, pin_in_gun, y) in self.gun_queue() {
+            if pin_idx > 0 && !label_all {
+                break;
+            }
+            let position = camera.world_to_screen(vec2(0., y));
+            let color = if use_white_text(self.color_map.display(pin_in_gun.color)) {
+                colors::WHITE
+            } else {
+                colors::BLACK
+            };
+            self.ui.text_at(
+                &format!("{}", loaded_pins - pin_idx),
+                position,
+                14.,
+                &TextParams {
+                    color,
+                    ..self.text_params.clone()
+                },
+            );
+        }
+    }
+    pub(crate) fn draw_hud(&self) {
+        if self.level_state == LevelState::Won && self.level_idx == self.levels.len() - 1 {
+            // win message
+            self.ui.text(
+                self.win_message,
+                Anchor::Top,
+                vec2(0., 48.),
+                24.,
+                &self.text_params,
+            );
+            self.ui.text(
+                "tap for a new run",
+                Anchor::Top,
+                vec2(0., 110.),
+                16.,
+                &self.text_params,
+            );
+        } else {
+            // level counter, where the old number floats away and the new one pops in
+            let (counter_idx, rise, alpha, scale) = match self.transition.as_ref() {
+                Some(transition) => match transition.spin_out_progress() {
+                    Some(progress) => (transition.old_level_idx, progress, 1. - progress, 1.),
+                    None => {
+                        let progress = transition.scale_in_progress();
+                        (self.level_idx, 0., progress, 1. + 0.3 * (1. - progress))
+                    }
+                },
+                None => (self.level_idx, 0., 1., 1.),
+            };
+            self.ui.text(
+                &format!("{} / {}", counter_idx + 1, self.levels.len()),
+                Anchor::Top,
+                vec2(0., 48. - rise * 48.),
+                36. * scale,
+                &TextParams {
+                    color: Color {
+                        a: alpha,
+                        ..self.text_params.color
+                    },
+                    ..self.text_params.clone()
+                },
+            );
+        }
+
+        // which day's challenge this is, and the best run of it so far
+        if let Some(date) = self.daily_date {
+            let label = match self.save_data.daily_results.get(&date.to_string()) {
+                Some(best) if best.assisted => format!("daily {date}  best {} *", best.score),
+                Some(best) => format!("daily {date}  best {}", best.score),
+                None => format!("daily {date}"),
+            };
+            self.ui
+                .text(&label, Anchor::Top, vec2(0., 82.), 18., &self.text_params);
+        }
+
+        self.draw_practice_hud();
+
+        // speedrun timer and delta against the personal best
+        if self.game_mode == GameMode::Speedrun {
+            self.ui.text(
+                &format_time(self.run_time()),
+                Anchor::Top,
+                vec2(0., 82.),
+                20.,
+                &self.text_params,
+            );
+            if let Some(delta) = self.split_delta() {
+                let seconds = delta.get::<second>();
+                self.ui.text(
+                    &format!(
+                        "{}{:.2}",
+                        if seconds > 0. { "+" } else { "-" },
+                        seconds.abs()
+                    ),
+                    Anchor::Top,
+                    vec2(134., 82.),
+                    16.,
+                    &TextParams {
+                        color: if seconds > 0. {
+                            Self::SPLIT_BEHIND
+                        } else {
+                            Self::SPLIT_AHEAD
+                        },
+                        ..self.text_params.clone()
+                    },
+                );
+            }
+        }
+
+        // score and pins left in the corners
+        let total: LevelStats = self.level_stats.iter().copied().sum();
+        self.ui.text(
+            &format!("score {}", total.score()),
+            Anchor::TopLeft,
+            vec2(16., 32.),
+            18.,
+            &self.text_params,
+        );
+        self.ui.text(
+            &format!("{} pins left", self.pin_gun.pins.len()),
+            Anchor::BottomLeft,
+            vec2(16., -24.),
+            18.,
+            &self.text_params,
+        );
+        if self.full_lives() > 0 {
+            self.ui.text(
+                &format!("{} spare lives", self.lives),
+                Anchor::BottomLeft,
+                vec2(16., -48.),
+                18.,
+                &self.text_params,
+            );
+        }
+
+        // menu button, drawn as three bars
+        let rect = self.menu_button_rect();
+        self.ui.button(rect, "", false, &self.text_params);
+        for bar in 0..3 {
+            let y = rect.y + rect.h * (0.3 + 0.2 * bar as f32);
+            draw_line(
+                rect.x + rect.w * 0.25,
+                y,
+                rect.x + rect.w * 0.75,
+                y,
+                self.ui.px(3.),
+                self.text_params.color,
+            );
+        }
+        // a dot that flashes on each beat in rhythm mode
+        if let Some(bpm) = self.rhythm_bpm() {
+            let pulse = 1. - self.mixer.beat_phase(bpm);
+            let center = self.ui.anchor(Anchor::BottomLeft, vec2(28., -84.));
+            let color = Color {
+                a: 0.3 + 0.7 * pulse,
+                ..self.theme.highlight_color
+            };
+            draw_circle(center.x, center.y, self.ui.px(6. + 4. * pulse), color);
+        }
+        if self.save_data.settings.muted {
+            let below = vec2(rect.center().x, rect.bottom() + self.ui.px(14.));
+            self.ui.text_at("muted", below, 14., &self.text_params);
+        }
+    }
+    // panels and messages drawn over everything else
+    pub(crate) fn draw_overlays(&self) {
+        let screen = self.ui.screen();
+        let white_text = TextParams {
+            color: colors::WHITE,
+            ..self.text_params.clone()
+        };
+
+        // results
+        if self.level_state == LevelState::Won {
+            if self.level_idx == self.levels.len() - 1 {
+                let total: LevelStats = self.level_stats.iter().copied().sum();
+                self.draw_results("total", &total.summary(), &white_text);
+            } else {
+                let title = format!("level {} results", self.level_idx + 1);
+                let summary = self.level_stats[self.level_idx].summary();
+                self.draw_results(&title, &summary, &white_text);
+            }
+        }
+
+        // prompt for the first interaction, which unlocks audio in browsers
+        if !self.audio_unlocked {
+            draw_rectangle(screen.x, screen.y, screen.w, screen.h, Self::OVERLAY);
+            self.ui
+                .text("tap to start", Anchor::Center, Vec2::ZERO, 36., &white_text);
+        }
+
+        // banner for messages that shouldn't interrupt the game, reaching up under any notch
+        if let Some(banner) = &self.banner {
+            let params = self.ui.text_params(14., &white_text);
+            let safe_area = self.ui.safe_area();
+            let line_height = self.ui.px(20.);
+            let lines = wrap_text(&banner.message, safe_area.w - self.ui.px(24.), &params);
+            let banner_height = safe_area.y + line_height * lines.len() as f32 + self.ui.px(10.);
+            draw_rectangle(
+                screen.x,
+                screen.y,
+                screen.w,
+                banner_height,
+                if banner.is_error {
+                    Self::BANNER_RED
+                } else {
+                    Self::BANNER_GRAY
+                },
+            );
+            for (line_idx, line) in lines.iter().enumerate() {
+                let y = safe_area.y + self.ui.px(5.) + line_height * (line_idx as f32 + 0.5);
+                draw_text_ex_center(line, safe_area.center().x, y, params.clone());
+            }
+        }
+    }
+    fn draw_results(&self, title: &str, lines: &[String], params: &TextParams) {
+        let line_height = 30.;
+        let panel_height = line_height * (lines.len() + 1) as f32 + 20.;
+        let panel = self
+            .ui
+            .rect(Anchor::Center, Vec2::ZERO, vec2(336., panel_height));
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, Self::OVERLAY);
+        let top = -panel_height / 2. + 10. + line_height / 2.;
+        self.ui
+            .text(title, Anchor::Center, vec2(0., top), 24., params);
+        for (line_idx, line) in lines.iter().enumerate() {
+            let y = top + line_height * (line_idx + 1) as f32;
+            self.ui.text(line, Anchor::Center, vec2(0., y), 20., params);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use macroquad::{color::colors, miniquad::date::now, prelude::*};
+
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+use crate::game::{
+    Anchor, Banner, GameEvent, GameState, LeaderboardEntry, LevelStats, NameEntry, Ui,
+    utils::format_time,
+};
+
+impl<'a> GameState<'a> {
+    const PANEL: Color = Color::new(0., 0., 0., 0.85);
+
+    fn leaderboard(&self) -> &[LeaderboardEntry] {
+        self.save_data
+            .leaderboards
+            .get(&self.pack_name)
+            .and_then(|boards| boards.get(&self.game_mode))
+            .map_or(&[], Vec::as_slice)
+    }
+    // a finished run that makes the table asks for a name before it's saved
+    pub(super) fn offer_leaderboard_entry(&mut self, event: &GameEvent) {
+        if !matches!(event, GameEvent::GameWon) || !self.full_run || !self.game_mode.is_ranked() {
+            return;
+        }
+        let total: LevelStats = self.level_stats.iter().copied().sum();
+        let entry = LeaderboardEntry {
+            name: self.save_data.last_name.clone(),
+            score: total.score(),
+            time: self.run_time(),
+            recorded_at: now(),
+            assisted: total.assisted,
+        };
+        if let Some(rank) = entry.rank(self.leaderboard(), self.game_mode) {
+            self.name_entry = Some(NameEntry { rank, entry });
+        }
+    }
+    fn name_entry_rect(&self) -> Rect {
+        self.ui.rect(Anchor::Center, vec2(0., 0.), vec2(320., 44.))
+    }
+    fn name_entry_save_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Center, vec2(-84., 74.), vec2(152., 44.))
+    }
+    fn name_entry_skip_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Center, vec2(84., 74.), vec2(152., 44.))
+    }
+    // typing a name swallows every other key, so it runs before anything else looks at input
+    pub(super) fn update_name_entry(&mut self) -> bool {
+        let save_clicked = Ui::clicked(self.name_entry_save_rect());
+        let skip_clicked = Ui::clicked(self.name_entry_skip_rect());
+        let Some(name_entry) = self.name_entry.as_mut() else {
+            return false;
+        };
+        while let Some(c) = get_char_pressed() {
+            if LeaderboardEntry::allowed_in_name(c)
+                && name_entry.entry.name.chars().count() < LeaderboardEntry::MAX_NAME_LENGTH
+            {
+                name_entry.entry.name.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            name_entry.entry.name.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) || skip_clicked {
+            self.name_entry = None;
+        } else if is_key_pressed(KeyCode::Enter) || save_clicked {
+            self.submit_name_entry();
+        }
+        true
+    }
+    fn submit_name_entry(&mut self) {
+        let Some(NameEntry { mut entry, .. }) = self.name_entry.take() else {
+            return;
+        };
+        entry.name = entry.name.trim().to_string();
+        if entry.name.is_empty() {
+            entry.name = LeaderboardEntry::DEFAULT_NAME.into();
+        }
+        self.save_data.last_name = entry.name.clone();
+        let entries = self
+            .save_data
+            .leaderboards
+            .entry(self.pack_name.clone())
+            .or_default()
+            .entry(self.game_mode)
+            .or_default();
+        entry.insert(entries, self.game_mode);
+        if let Err(e) = self.save_data.save() {
+            self.banner = Some(Banner::error(e));
+        }
+        self.leaderboard_open = true;
+    }
+    fn leaderboard_close_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(0., -90.), vec2(112., 44.))
+    }
+    fn leaderboard_csv_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(-124., -90.), vec2(112., 44.))
+    }
+    fn leaderboard_json_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(124., -90.), vec2(112., 44.))
+    }
+    // returns true while the leaderboard is open so the game stays paused, and on the frame it
+    // closes
+    pub(super) fn update_leaderboard_screen(&mut self) -> bool {
+        let was_open = self.leaderboard_open;
+        if !was_open {
+            self.leaderboard_open = self.settings_menu.is_none()
+                && !self.achievements_open
+                && is_key_pressed(KeyCode::L);
+            return self.leaderboard_open;
+        }
+        if is_key_pressed(KeyCode::C) || Ui::clicked(self.leaderboard_csv_rect()) {
+            let csv = LeaderboardEntry::to_csv(&self.pack_name, self.game_mode, self.leaderboard());
+            self.export_leaderboard(&csv, "csv");
+        }
+        if is_key_pressed(KeyCode::J) || Ui::clicked(self.leaderboard_json_rect()) {
+            let json =
+                LeaderboardEntry::to_json(&self.pack_name, self.game_mode, self.leaderboard());
+            self.export_leaderboard(&json, "json");
+        }
+        self.leaderboard_open = !(is_key_pressed(KeyCode::Escape)
+            || is_key_pressed(KeyCode::L)
+            || Ui::clicked(self.leaderboard_close_rect()));
+        true
+    }
+    fn export_leaderboard(&mut self, contents: &str, extension: &str) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = format!(
+                "pinwheel-leaderboard-{}-{}-{}.{extension}",
+                self.pack_name,
+                self.game_mode.name(),
+                now() as u64
+            );
+            self.banner = Some(match std::fs::write(&path, contents) {
+                Ok(()) => Banner::info(format!("Exported leaderboard to {path}")),
+                Err(e) => Banner::error(format!("Failed to write {path}: {e}")),
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            web::clipboard_write(contents);
+            self.banner = Some(Banner::info(format!(
+                "Copied leaderboard {extension} to clipboard"
+            )));
+        }
+    }
+    pub(crate) fn draw_name_entry(&self) {
+        let Some(name_entry) = &self.name_entry else {
+            return;
+        };
+        let white_text = TextParams {
+            color: colors::WHITE,
+            ..self.text_params.clone()
+        };
+        let panel = self
+            .ui
+            .rect(Anchor::Center, vec2(0., 10.), vec2(360., 240.));
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, Self::PANEL);
+        self.ui.text(
+            "new record",
+            Anchor::Center,
+            vec2(0., -80.),
+            28.,
+            &white_text,
+        );
+        let detail = format!(
+            "#{}  score {}  {}",
+            name_entry.rank + 1,
+            name_entry.entry.score,
+            format_time(name_entry.entry.time)
+        );
+        self.ui
+            .text(&detail, Anchor::Center, vec2(0., -46.), 18., &white_text);
+        let field = self.name_entry_rect();
+        draw_rectangle(
+            field.x,
+            field.y,
+            field.w,
+            field.h,
+            Color::new(1., 1., 1., 0.12),
+        );
+        draw_rectangle_lines(
+            field.x,
+            field.y,
+            field.w,
+            field.h,
+            self.ui.px(2.),
+            self.theme.highlight_color,
+        );
+        let name = format!("{}_", name_entry.entry.name);
+        self.ui.text_at(&name, field.center(), 22., &white_text);
+        self.ui
+            .button(self.name_entry_save_rect(), "save", false, &white_text);
+        self.ui
+            .button(self.name_entry_skip_rect(), "skip", false, &white_text);
+    }
+    pub(crate) fn draw_leaderboard_screen(&self) {
+        if !self.leaderboard_open {
+            return;
+        }
+        let screen = self.ui.screen();
+        draw_rectangle(screen.x, screen.y, screen.w, screen.h, Self::PANEL);
+        let white_text = TextParams {
+            color: colors::WHITE,
+            ..self.text_params.clone()
+        };
+        self.ui
+            .text("leaderboard", Anchor::Top, vec2(0., 96.), 36., &white_text);
+        let subtitle = format!("{} - {}", self.pack_name, self.game_mode.name());
+        self.ui
+            .text(&subtitle, Anchor::Top, vec2(0., 130.), 18., &white_text);
+        let entries = self.leaderboard();
+        if entries.is_empty() {
+            self.ui.text(
+                "no records yet",
+                Anchor::Top,
+                vec2(0., 180.),
+                20.,
+                &white_text,
+            );
+        }
+        for (rank, entry) in entries.iter().enumerate() {
+            let y = 180. + rank as f32 * 34.;
+            let params = TextParams {
+                color: if entry.name == self.save_data.last_name {
+                    self.theme.highlight_color
+                } else {
+                    colors::WHITE
+                },
+                ..white_text.clone()
+            };
+            let columns = [
+                (-160., format!("{}.", rank + 1)),
+                (
+                    -70.,
+                    entry.name.clone() + if entry.assisted { " *" } else { "" },
+                ),
+                (70., entry.score.to_string()),
+                (150., format_time(entry.time)),
+            ];
+            for (x, text) in columns {
+                self.ui.text(&text, Anchor::Top, vec2(x, y), 18., &params);
+            }
+        }
+        if entries.iter().any(|entry| entry.assisted) {
+            let y = 180. + entries.len() as f32 * 34.;
+            self.ui
+                .text("* assists used", Anchor::Top, vec2(0., y), 14., &white_text);
+        }
+        self.ui
+            .button(self.leaderboard_csv_rect(), "csv", false, &white_text);
+        self.ui
+            .button(self.leaderboard_close_rect(), "close", false, &white_text);
+        self.ui
+            .button(self.leaderboard_json_rect(), "json", false, &white_text);
+    }
+}
@@ -0,0 +1,130 @@
+use macroquad::{color::colors, prelude::*};
+
+use crate::game::{Anchor, Banner, GameState, SettingsItem, Ui};
+
+impl<'a> GameState<'a> {
+    pub(crate) fn menu_button_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::TopRight, vec2(-12., 12.), vec2(40., 40.))
+    }
+    // items squeeze together on short screens so the last one stays above the close button
+    fn settings_item_rect(&self, item_idx: usize) -> Rect {
+        let available = self.ui.safe_area().h / self.ui.scale - 150. - 142.;
+        let spacing = (available / SettingsItem::ALL.len() as f32).clamp(24., 48.);
+        self.ui.rect(
+            Anchor::Top,
+            vec2(0., 150. + item_idx as f32 * spacing),
+            vec2(320., spacing * 40. / 48.),
+        )
+    }
+    fn settings_achievements_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(-88., -90.), vec2(160., 44.))
+    }
+    fn settings_close_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::Bottom, vec2(88., -90.), vec2(160., 44.))
+    }
+    // returns true while the settings menu is open so the game stays paused, and on the frame it
+    // closes so the click that closed it doesn't also fire a pin
+    pub(crate) fn update_settings_menu(&mut self) -> bool {
+        let was_open = self.settings_menu.is_some();
+        // the achievements screen takes over from the settings menu
+        let show_achievements = was_open && Ui::clicked(self.settings_achievements_rect());
+        let toggled = is_key_pressed(KeyCode::Escape)
+            || show_achievements
+            || match self.settings_menu {
+                Some(_) => Ui::clicked(self.settings_close_rect()),
+                None => Ui::clicked(self.menu_button_rect()),
+            };
+        self.achievements_open = show_achievements;
+        if toggled {
+            self.settings_menu = match self.settings_menu {
+                Some(_) => {
+                    if let Err(e) = self.save_data.save() {
+                        self.banner = Some(Banner::error(e));
+                    }
+                    None
+                }
+                None => Some(0),
+            };
+        }
+        let Some(mut selected) = self.settings_menu else {
+            return was_open;
+        };
+        if is_key_pressed(KeyCode::Down) {
+            selected = (selected + 1) % SettingsItem::ALL.len();
+        }
+        if is_key_pressed(KeyCode::Up) {
+            selected = (selected + SettingsItem::ALL.len() - 1) % SettingsItem::ALL.len();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            SettingsItem::ALL[selected].cycle(&mut self.save_data.settings);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            SettingsItem::ALL[selected].adjust(&mut self.save_data.settings, 1);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            SettingsItem::ALL[selected].adjust(&mut self.save_data.settings, -1);
+        }
+        for (item_idx, item) in SettingsItem::ALL.iter().enumerate() {
+            if Ui::clicked(self.settings_item_rect(item_idx)) {
+                selected = item_idx;
+                item.cycle(&mut self.save_data.settings);
+            }
+        }
+        self.settings_menu = Some(selected);
+        true
+    }
+    pub(crate) fn draw_settings_menu(&self) {
+        let Some(selected) = self.settings_menu else {
+            return;
+        };
+        let screen = self.ui.screen();
+        draw_rectangle(
+            screen.x,
+            screen.y,
+            screen.w,
+            screen.h,
+            Color::new(0., 0., 0., 0.75),
+        );
+        let white_text = TextParams {
+            color: colors::WHITE,
+            ..self.text_params.clone()
+        };
+        self.ui
+            .text("settings", Anchor::Top, vec2(0., 96.), 36., &white_text);
+        for (item_idx, item) in SettingsItem::ALL.iter().enumerate() {
+            let label = item.label(&self.save_data.settings);
+            let params = TextParams {
+                color: if item_idx == selected {
+                    self.theme.highlight_color
+                } else {
+                    colors::WHITE
+                },
+                ..self.text_params.clone()
+            };
+            self.ui.button(
+                self.settings_item_rect(item_idx),
+                &label,
+                item_idx == selected,
+                &params,
+            );
+        }
+        self.ui.button(
+            self.settings_achievements_rect(),
+            "achievements",
+            false,
+            &white_text,
+        );
+        self.ui
+            .button(self.settings_close_rect(), "close", false, &white_text);
+        self.ui.text(
+            "arrows or tap to change, esc to close",
+            Anchor::Bottom,
+            vec2(0., -40.),
+            16.,
+            &white_text,
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use std::{error::Error, sync::LazyLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    error::Error,
+    sync::LazyLock,
+};
 
 use macroquad::{
-    audio::play_sound_once,
     color::colors,
     miniquad::date::now,
     prelude::*,
@@ -9,9 +14,26 @@ use macroquad::{
 };
 use uom::si::{f32::Time, time::second};
 
-use crate::game::{GameState, GlWrapper, Level, LevelState, PinGun, SoundData, Sounds, Spinner};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::game::LevelWatcher;
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+use crate::game::{
+    Banner, CalendarDate, ColorMap, GameEvent, GameMode, GameState, GlWrapper, LaunchOptions,
+    Level, LevelState, LevelStats, Mixer, ParticleSystem, PinFlying, PinGun, PinInGun, SaveData,
+    Spinner, SpinnerMesh, Sprites, Theme, ThemeAssets, Transition, Ui,
+};
 
+mod achievements;
+mod checkpoint;
+mod daily;
+mod events;
+mod hud;
+mod leaderboard;
+mod menu;
+mod practice;
 mod render;
+mod speedrun;
 mod step;
 
 static FONT: LazyLock<Font> = LazyLock::new(|| {
@@ -30,16 +52,35 @@ impl<'a> GameState<'a> {
         "you have mastered this game",
         "you are victorious",
     ];
-    pub async fn new(gl: InternalGlContext<'_>) -> Result<GameState<'_>, Box<dyn Error>> {
-        let levels_str = include_str!("../../../assets/levels.json");
-        let levels = match serde_json::from_str::<Vec<Level>>(levels_str) {
-            Ok(l) => l,
-            Err(e) => return Err(format!("Failed to parse level definitions: {e}").into()),
+    pub async fn new(
+        gl: InternalGlContext<'_>,
+        options: LaunchOptions,
+    ) -> Result<GameState<'_>, Box<dyn Error>> {
+        let daily_date = (options.game_mode == GameMode::Daily).then(CalendarDate::today);
+        let built_in_pack =
+            daily_date.is_none() && options.level_pack.is_none() && options.shared_level.is_none();
+        let (pack_name, pack) = match daily_date {
+            Some(date) => (date.pack_name(), Level::daily_pack(date)),
+            None => Level::load_pack(&options).await?,
         };
-        if levels.is_empty() {
-            return Err("No levels found".into());
-        }
-        srand((now() * 1000.) as u64);
+        let levels = pack.levels;
+        let save_data = SaveData::load();
+        // speedruns and dailies always start from the top and use a fixed seed so every run is the
+        // same, while practice picks up wherever the campaign is
+        let level_idx = match options.game_mode {
+            GameMode::Campaign | GameMode::Practice => save_data
+                .progress
+                .get(&pack_name)
+                .map_or(0, |&idx| idx.min(levels.len() - 1)),
+            GameMode::Speedrun | GameMode::Daily => 0,
+        };
+        srand(match daily_date {
+            Some(date) => date.seed(),
+            None => match options.game_mode {
+                GameMode::Speedrun => Self::SPEEDRUN_SEED,
+                GameMode::Campaign | GameMode::Daily | GameMode::Practice => (now() * 1000.) as u64,
+            },
+        });
         let mut game = GameState {
             gl: GlWrapper(gl),
             startup_complete: false,
@@ -52,33 +93,242 @@ impl<'a> GameState<'a> {
             spinner: Spinner::default(),
             pin_gun: PinGun::default(),
             flying_pins: vec![],
+            game_mode: options.game_mode,
+            daily_date,
+            pack_name,
+            built_in_pack,
             levels,
-            level_idx: 0,
+            level_idx,
             level_state: LevelState::default(),
-            sound_data: SoundData::load().await,
+            level_stats: Vec::new(),
+            transition: None,
+            spinner_mesh: SpinnerMesh::default(),
+            color_map: ColorMap::default(),
+            splits: Vec::new(),
+            particles: ParticleSystem::default(),
+            settings_menu: None,
+            achievements_open: false,
+            leaderboard_open: false,
+            name_entry: None,
+            full_run: true,
+            toasts: VecDeque::new(),
+            clean_attempt: true,
+            // browsers refuse to play audio until the player interacts with the page
+            audio_unlocked: cfg!(not(target_arch = "wasm32")),
+            mixer: Mixer::default(),
+            save_data,
+            banner: None,
+            pack_theme: pack.theme,
+            pack_sprites: pack.sprites,
+            pack_music: pack.music,
+            pack_sounds: pack.sounds,
+            pack_bpm: pack.bpm,
+            combo: 0,
+            lives: 0,
+            checkpoint: None,
+            event_handlers: Vec::new(),
+            event_banners: Vec::new(),
+            save_after_event: false,
+            shot_history: Vec::new(),
+            theme: Theme::default(),
+            theme_assets: ThemeAssets::default(),
+            loaded_theme: None,
+            fonts: HashMap::new(),
+            ui: Ui::default(),
+            physics_lag: Time::new::<second>(0.),
+            #[cfg(not(target_arch = "wasm32"))]
+            level_watcher: match (&options.shared_level, daily_date) {
+                (None, None) => options.level_pack.map(PathBuf::from).map(LevelWatcher::new),
+                _ => None,
+            },
         };
+        game.subscribe(GameState::play_event_sound);
+        game.subscribe(GameState::spawn_event_particles);
+        game.subscribe(GameState::record_event_stats);
+        game.subscribe(GameState::save_checkpoint);
+        game.subscribe(GameState::unlock_achievements);
+        game.subscribe(GameState::record_daily_result);
+        game.subscribe(GameState::offer_leaderboard_entry);
+        game.reset_stats();
         game.load_level(game.level_idx);
         Ok(game)
     }
+    // like load_level, but animates out of the current level first
+    fn transition_to_level(&mut self, level_idx: usize) {
+        let old_spinner = self.spinner.clone();
+        let old_color_map = self.color_map.clone();
+        let old_level_idx = self.level_idx;
+        self.level_idx = level_idx;
+        self.load_level(level_idx);
+        self.transition = Some(Transition::new(
+            old_spinner,
+            old_color_map,
+            old_level_idx,
+            self.pin_gun.pins.len(),
+        ));
+    }
     fn load_level(&mut self, level_idx: usize) {
         let level = &self.levels[level_idx];
         self.spinner = level.spinner.clone();
+        self.color_map = ColorMap::new(level, self.save_data.settings.palette, &self.theme);
         self.pin_gun.pins = level.pins_in_gun.clone();
         self.flying_pins.clear();
+        self.particles.clear();
+        self.combo = 0;
+        self.lives = self.full_lives();
+        self.checkpoint = None;
+        self.shot_history.clear();
+        self.clean_attempt = true;
+        self.physics_lag = Time::new::<second>(0.);
+        self.transition = None;
         self.level_state = LevelState::Playing;
     }
-    fn play_sound(&self, sound: Sounds) {
-        match sound {
-            Sounds::PinFire => play_sound_once(&self.sound_data.pin_fire),
-            Sounds::PinLand => play_sound_once(&self.sound_data.pin_land),
-            Sounds::LoseLevel => play_sound_once(&self.sound_data.lose_level),
-            Sounds::NextLevel => play_sound_once(&self.sound_data.next_level),
-            Sounds::WinLevel => play_sound_once(&self.sound_data.win_level),
-            Sounds::WinGame => play_sound_once(&self.sound_data.win_game),
+    // back to the first level with fresh stats, so the run can make the leaderboard
+    fn start_new_run(&mut self) {
+        self.transition_to_level(0);
+        self.reset_stats();
+        self.save_progress();
+        self.emit(GameEvent::LevelStarted { retry: false });
+    }
+    fn reset_stats(&mut self) {
+        self.level_stats = vec![LevelStats::default(); self.levels.len()];
+        self.splits.clear();
+        self.full_run = self.level_idx == 0;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_levels(&mut self, dt: Time) {
+        let Some(level_watcher) = self.level_watcher.as_mut() else {
+            return;
         };
+        match level_watcher.poll(dt) {
+            Some(Ok(pack)) => {
+                self.pack_theme = pack.theme;
+                self.pack_sprites = pack.sprites;
+                self.pack_music = pack.music;
+                self.pack_sounds = pack.sounds;
+                self.pack_bpm = pack.bpm;
+                self.levels = pack.levels;
+                self.loaded_theme = None;
+                self.level_idx = self.level_idx.min(self.levels.len() - 1);
+                self.reset_stats();
+                self.load_level(self.level_idx);
+                self.banner = Some(Banner::info("Reloaded levels"));
+            }
+            Some(Err(e)) => self.banner = Some(Banner::error(e)),
+            None => (),
+        }
+    }
+    fn save_progress(&mut self) {
+        if self.game_mode != GameMode::Campaign {
+            return;
+        }
+        self.save_data
+            .progress
+            .insert(self.pack_name.clone(), self.level_idx);
+        if let Err(e) = self.save_data.save() {
+            self.banner = Some(Banner::error(e));
+        }
+    }
+    fn copy_level_code(&mut self) {
+        let code = self.levels[self.level_idx].to_code();
+        #[cfg(not(target_arch = "wasm32"))]
+        miniquad::window::clipboard_set(&code);
+        #[cfg(target_arch = "wasm32")]
+        web::clipboard_write(&code);
+        self.banner = Some(Banner::info("Copied level code to clipboard"));
+    }
+    fn paste_level_code(&mut self) {
+        let Some(code) = miniquad::window::clipboard_get() else {
+            self.banner = Some(Banner::error("Clipboard is empty"));
+            return;
+        };
+        match Level::from_code(&code) {
+            Ok(level) => {
+                self.pack_name = Level::SHARED_PACK_NAME.into();
+                self.built_in_pack = false;
+                self.pack_theme = None;
+                self.pack_sprites = Sprites::default();
+                self.pack_music = None;
+                self.pack_sounds = BTreeMap::new();
+                self.pack_bpm = None;
+                self.levels = vec![level];
+                self.loaded_theme = None;
+                self.level_idx = 0;
+                self.reset_stats();
+                self.load_level(self.level_idx);
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.level_watcher = None;
+                }
+                self.banner = Some(Banner::info("Loaded level from clipboard"));
+            }
+            Err(e) => self.banner = Some(Banner::error(e)),
+        }
+    }
+    // the settings choice wins over the pack's theme
+    fn theme_source(&self) -> String {
+        self.save_data
+            .settings
+            .theme
+            .clone()
+            .or_else(|| self.pack_theme.clone())
+            .unwrap_or_else(|| Theme::DEFAULT_NAME.into())
+    }
+    async fn load_theme(&mut self, source: String) {
+        let (theme, mut theme_assets) = match Theme::load(&source, &mut self.fonts).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.banner = Some(Banner::error(e));
+                (Theme::default(), ThemeAssets::default())
+            }
+        };
+        // pack sprite paths were already resolved against the pack when it was parsed
+        if let Err(e) = self.pack_sprites.load(None, &mut theme_assets).await {
+            self.banner = Some(Banner::error(e));
+        }
+        self.text_params.font = Some(theme_assets.font.unwrap_or(&FONT));
+        self.text_params.color = theme.text_color;
+        self.color_map.theme_colors = theme.palette.iter().map(|color| color.0).collect();
+        self.spinner_mesh.face_texture = theme_assets.spinner_texture.clone();
+        self.spinner_mesh.pin_texture = theme_assets.pin_sprite.clone();
+        self.theme = theme;
+        self.theme_assets = theme_assets;
+        // remember failed themes too so they aren't retried every frame
+        self.loaded_theme = Some(source);
+    }
+    // rhythm mode needs both the setting and a pack with music and a tempo
+    fn rhythm_bpm(&self) -> Option<f32> {
+        self.pack_bpm
+            .filter(|_| self.save_data.settings.rhythm_mode && self.mixer.music_playing())
+    }
+    // pins leave the gun at the speed the pin speed setting asks for
+    fn launch(&self, pin: PinInGun) -> PinFlying {
+        let mut pin: PinFlying = pin.into();
+        pin.vertical_velocity *= self.save_data.settings.pin_speed;
+        pin
     }
     pub async fn run(&mut self) -> ! {
         loop {
+            let theme_source = self.theme_source();
+            if self.loaded_theme.as_ref() != Some(&theme_source) {
+                self.load_theme(theme_source).await;
+            }
+            if self.audio_unlocked {
+                let level_sounds = &self.levels[self.level_idx].sounds;
+                let errors = self
+                    .mixer
+                    .select_sounds(&self.pack_sounds, level_sounds, &self.save_data.settings)
+                    .await;
+                if let Some(e) = errors.into_iter().next() {
+                    self.banner = Some(Banner::error(e));
+                }
+            }
+            if self.audio_unlocked && self.mixer.loaded_music() != self.pack_music.as_deref() {
+                let music = self.pack_music.clone();
+                if let Err(e) = self.mixer.load_music(music, &self.save_data.settings).await {
+                    self.banner = Some(Banner::error(e));
+                }
+            }
             self.step(Time::new::<second>(get_frame_time()));
             self.render();
             next_frame().await
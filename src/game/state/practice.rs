@@ -0,0 +1,72 @@
+use macroquad::prelude::*;
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{Anchor, Banner, GameState, LevelState, LevelStats, ShotSnapshot};
+
+impl<'a> GameState<'a> {
+    // sits just left of the menu button
+    pub(super) fn undo_button_rect(&self) -> Rect {
+        self.ui
+            .rect(Anchor::TopRight, vec2(-60., 12.), vec2(64., 40.))
+    }
+    // called just before a pin leaves the gun, and only practice runs keep the history
+    pub(super) fn snapshot_shot(&mut self) {
+        if self.game_mode.is_ranked() || self.pin_gun.pins.is_empty() {
+            return;
+        }
+        self.shot_history.push(ShotSnapshot {
+            spinner: self.spinner.clone(),
+            pins_in_gun: self.pin_gun.pins.clone(),
+            flying_pins: self.flying_pins.clone(),
+            lives: self.lives,
+            checkpoint: self.checkpoint.clone(),
+            stats: self.level_stats[self.level_idx],
+        });
+    }
+    // rewinds to just before the last pin was fired, even after it lost the level
+    pub(super) fn undo_shot(&mut self) {
+        if self.game_mode.is_ranked() {
+            self.banner = Some(Banner::info("Undo only works in practice mode"));
+            return;
+        }
+        if self.level_state == LevelState::Won {
+            return;
+        }
+        let Some(snapshot) = self.shot_history.pop() else {
+            return;
+        };
+        self.spinner = snapshot.spinner;
+        self.pin_gun.pins = snapshot.pins_in_gun;
+        self.flying_pins = snapshot.flying_pins;
+        self.lives = snapshot.lives;
+        self.checkpoint = snapshot.checkpoint;
+        // the clock keeps running, but everything the shot counted is taken back
+        let stats = &mut self.level_stats[self.level_idx];
+        *stats = LevelStats {
+            time: stats.time,
+            ..snapshot.stats
+        };
+        self.combo = 0;
+        self.physics_lag = Time::new::<second>(0.);
+        self.level_state = LevelState::Playing;
+    }
+    pub(super) fn draw_practice_hud(&self) {
+        if self.game_mode.is_ranked() {
+            return;
+        }
+        self.ui.text(
+            "practice",
+            Anchor::Top,
+            vec2(0., 82.),
+            18.,
+            &self.text_params,
+        );
+        let highlighted = !self.shot_history.is_empty() && self.level_state != LevelState::Won;
+        self.ui.button(
+            self.undo_button_rect(),
+            "undo",
+            highlighted,
+            &self.text_params,
+        );
+    }
+}
@@ -1,28 +1,25 @@
-use std::f32::consts::FRAC_PI_2;
-
 use macroquad::{color::colors, miniquad::window::screen_size, prelude::*};
 use uom::si::{
-    angle::{radian, revolution},
+    angle::{degree, radian, revolution},
     f32::Angle,
     length::meter,
 };
 
 use crate::game::{
-    GameState, LevelState, Sector,
-    utils::{CircularSectorParams, draw_circular_sector, draw_text_ex_center, use_white_text},
+    Background, Collision, ColorMap, GameState, LevelState, PinInGun, Spinner, Ui,
+    utils::{draw_glyph, use_white_text},
 };
 
 impl<'a> GameState<'a> {
-    const TARGET_HEIGHT: f32 = 720.;
-    const TARGET_WIDTH: f32 = 480.;
-    const TARGET_ASPECT_RATIO: f32 = Self::TARGET_HEIGHT / Self::TARGET_WIDTH;
+    const TARGET_ASPECT_RATIO: f32 = Ui::TARGET_HEIGHT / Ui::TARGET_WIDTH;
     const TARGET_BOUNDING_BOX_METERS: Rect = Rect::new(-5., -10., 10., 15.);
-
-    const SKY_BLUE: Color = Color::from_hex(0x3CA7D5);
+    const PREVIEW_MISS: Color = Color::from_hex(0xD93A3A);
+    const AIM_ASSIST_HIT: Color = Color::from_hex(0x1FAA59);
+    const AIM_ASSIST_SAMPLE_SECONDS: f32 = 1. / 60.;
 
     pub fn render(&mut self) {
         if !self.startup_complete {
-            request_new_screen_size(Self::TARGET_WIDTH, Self::TARGET_HEIGHT);
+            request_new_screen_size(Ui::TARGET_WIDTH, Ui::TARGET_HEIGHT);
             self.startup_complete = true;
             return;
         }
@@ -53,114 +50,273 @@ impl<'a> GameState<'a> {
         } else {
             Self::TARGET_BOUNDING_BOX_METERS
         };
-        let camera = Camera2D::from_display_rect(world_bounding_box_meters);
+        let shake_offset = self.particles.shake_offset();
+        let camera = Camera2D::from_display_rect(Rect {
+            x: world_bounding_box_meters.x + shake_offset.x,
+            y: world_bounding_box_meters.y + shake_offset.y,
+            ..world_bounding_box_meters
+        });
         set_camera(&camera);
 
-        // set text parameters based on updated camera
-        let (font_size, font_scale, font_aspect) = camera_font_scale(1.);
-        self.text_params = TextParams {
-            font_size,
-            font_scale,
-            // text appears upside down and backwards unless I include these tweaks, not sure why
-            font_scale_aspect: -font_aspect,
-            rotation: Angle::new::<revolution>(0.5).get::<radian>(),
-            ..self.text_params
-        };
-
         // clear screen so we can draw the next frame
-        clear_background(Self::SKY_BLUE);
+        self.draw_background(world_bounding_box_meters);
 
-        if self.level_state == LevelState::Won && self.level_idx == self.levels.len() - 1 {
-            // win message
-            draw_text_ex_center(
-                self.win_message,
-                0.,
-                4.,
-                TextParams {
-                    font_size: 24,
-                    ..self.text_params
-                },
-            );
-        } else {
-            // level counter
-            draw_text_ex_center(
-                &format!("{} / {}", self.level_idx + 1, self.levels.len()),
-                0.,
-                4.,
-                TextParams {
-                    font_size: 36,
-                    ..self.text_params
-                },
-            );
+        // spinner, which spins out and scales back in between levels
+        let pixels_per_meter = height / world_bounding_box_meters.h;
+        let (spinner, color_map, scale, rotation) = match self.transition.as_ref() {
+            Some(transition) => match transition.spin_out_progress() {
+                Some(progress) => (
+                    &transition.old_spinner,
+                    &transition.old_color_map,
+                    1. - progress * progress,
+                    transition.old_spinner.angular_position
+                        + Angle::new::<revolution>(progress * progress),
+                ),
+                None => {
+                    let progress = transition.scale_in_progress();
+                    (
+                        &self.spinner,
+                        &self.color_map,
+                        1. - (1. - progress) * (1. - progress),
+                        self.spinner.angular_position,
+                    )
+                }
+            },
+            None => (
+                &self.spinner,
+                &self.color_map,
+                1.,
+                self.spinner.angular_position,
+            ),
+        };
+        self.spinner_mesh.draw(
+            &mut self.gl,
+            spinner,
+            color_map,
+            pixels_per_meter,
+            scale,
+            rotation,
+        );
+        if self.save_data.settings.color_glyphs {
+            Self::draw_spinner_glyphs(spinner, color_map, scale, rotation);
         }
 
-        // spinner sectors
-        for sector in self.spinner.sectors.iter() {
-            let n = ((sector.angle_stop - sector.angle_start).get::<revolution>()
-                * Sector::TRIANGLES_PER_TURN as f32) as u16;
-            let params = CircularSectorParams {
-                n,
-                radius: self.spinner.radius.get::<meter>(),
-                rotation: (sector.angle_start + self.spinner.angular_position).get::<radian>(),
-                arc: (sector.angle_stop - sector.angle_start).get::<radian>(),
-                color: sector.color,
+        // pin gun, which refills one pin at a time between levels
+        match &self.theme_assets.gun_sprite {
+            Some(texture) => draw_texture_ex(
+                texture,
+                -0.5,
+                -10.,
+                colors::WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(1., 5.)),
+                    flip_y: true,
+                    ..Default::default()
+                },
+            ),
+            None => draw_rectangle(-0.5, -10., 1., 5., self.theme.gun_color),
+        }
+        let spacing = self.gun_pin_spacing();
+        let mut previous: Option<(&PinInGun, f32)> = None;
+        let mut color_changes = 0;
+        for (pin_idx, pin_in_gun, y) in self.gun_queue() {
+            // mark where the color changes, with the first change standing out
+            if let Some((previous_pin, previous_y)) = previous
+                && previous_pin.color != pin_in_gun.color
+            {
+                let marker_y = (previous_y + y) / 2.;
+                let (marker_color, thickness) = match color_changes {
+                    0 => (self.theme.highlight_color, 0.08),
+                    _ => (colors::WHITE, 0.03),
+                };
+                draw_line(-0.45, marker_y, 0.45, marker_y, thickness, marker_color);
+                color_changes += 1;
+            }
+            previous = Some((pin_in_gun, y));
+            let radius = if pin_idx == 0 {
+                0.25
+            } else {
+                (spacing * 0.4).min(0.25)
             };
-            draw_circular_sector(0., 0., params, &mut self.gl);
+            draw_circle(0., y, radius, self.color_map.display(pin_in_gun.color));
+            if self.save_data.settings.color_glyphs
+                && let Some(glyph) = self.color_map.glyph(pin_in_gun.color)
+            {
+                draw_glyph(glyph, 0.9, y, (spacing * 0.8).min(0.4), colors::WHITE);
+            }
         }
 
-        // spinner pins
-        for spinner_pin in self.spinner.pins.iter() {
-            let x = (self.spinner.radius
-                * (spinner_pin.angular_position + self.spinner.angular_position).cos())
-            .get::<meter>();
-            let y = (self.spinner.radius
-                * (spinner_pin.angular_position + self.spinner.angular_position).sin())
-            .get::<meter>();
-            draw_rectangle_ex(
-                x,
-                y,
-                0.2,
-                spinner_pin.length.get::<meter>(),
-                DrawRectangleParams {
-                    offset: vec2(0.5, 0.),
-                    rotation: (spinner_pin.angular_position + self.spinner.angular_position)
-                        .get::<radian>()
-                        - FRAC_PI_2,
-                    color: spinner_pin.color,
-                },
-            );
+        // what the next pin would hit, and a ghost of where it would end up if fired now
+        if self.save_data.settings.aim_assist {
+            self.draw_aim_assist();
         }
-
-        // pin gun
-        draw_rectangle(-0.5, -10., 1., 5., colors::GRAY);
-        for (pin_idx, pin_in_gun) in self.pin_gun.pins.iter().rev().take(5).enumerate() {
-            let y = -5.5 - (pin_idx as f32 * 1.);
-            draw_circle(0., y, 0.25, pin_in_gun.color);
-            draw_text_ex_center(
-                &format!("{}", self.pin_gun.pins.len() - pin_idx),
-                0.,
-                y,
-                TextParams {
-                    font_size: 14,
-                    color: if use_white_text(pin_in_gun.color) {
-                        colors::WHITE
-                    } else {
-                        colors::BLACK
-                    },
-                    ..self.text_params
-                },
-            );
+        if self.save_data.settings.landing_preview {
+            self.draw_landing_preview();
         }
 
         // flying pins
         for flying_pin in self.flying_pins.iter() {
-            draw_rectangle(
-                -0.1,
-                flying_pin.vertical_position.get::<meter>(),
-                0.2,
-                -1.,
-                flying_pin.color,
+            let color = self.color_map.display(flying_pin.color);
+            let y = flying_pin.vertical_position.get::<meter>();
+            match &self.theme_assets.pin_sprite {
+                Some(texture) => draw_texture_ex(
+                    texture,
+                    -0.1,
+                    y - 1.,
+                    color,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(0.2, 1.)),
+                        flip_y: true,
+                        ..Default::default()
+                    },
+                ),
+                None => draw_rectangle(-0.1, y, 0.2, -1., color),
+            }
+            if self.save_data.settings.color_glyphs
+                && let Some(glyph) = self.color_map.glyph(flying_pin.color)
+            {
+                draw_glyph(glyph, 0.45, y - 0.5, 0.4, colors::WHITE);
+            }
+        }
+
+        self.particles.draw();
+
+        // everything from here on is laid out in screen pixels
+        set_default_camera();
+        self.draw_gun_labels(&camera);
+        self.draw_hud();
+        self.draw_toast();
+        self.draw_settings_menu();
+        self.draw_achievements_screen();
+        self.draw_overlays();
+        self.draw_leaderboard_screen();
+        self.draw_name_entry();
+    }
+    // highlights the sector or blocker the next pin would hit, and with a timing window set, only
+    // while a safe shot is at most that far away
+    fn draw_aim_assist(&self) {
+        if self.level_state != LevelState::Playing || self.transition.is_some() {
+            return;
+        }
+        let Some(next_pin) = self.pin_gun.pins.last() else {
+            return;
+        };
+        let next_pin = self.launch(*next_pin);
+        let Some(prediction) = self.spinner.predict(&self.flying_pins, next_pin.clone()) else {
+            return;
+        };
+        let window = self.save_data.settings.aim_assist_window;
+        if window > 0. {
+            let window_ticks = (window / Spinner::TICK_SECONDS) as usize;
+            let sample_ticks = (Self::AIM_ASSIST_SAMPLE_SECONDS / Spinner::TICK_SECONDS) as usize;
+            let safe_shot_soon = self.spinner.safe_shot_within(
+                &self.flying_pins,
+                &next_pin,
+                window_ticks,
+                sample_ticks,
             );
+            if !safe_shot_soon {
+                return;
+            }
+        }
+        let color = match prediction.collision {
+            Collision::Landed { .. } => Self::AIM_ASSIST_HIT,
+            Collision::WrongSector | Collision::Blocked => Self::PREVIEW_MISS,
+        };
+        let start = (self.spinner.angular_position + prediction.arc.start).get::<degree>();
+        let sweep = (prediction.arc.end - prediction.arc.start).get::<degree>();
+        let radius = self.spinner.radius.get::<meter>();
+        draw_arc(0., 0., 128, radius + 0.05, start, 0.2, sweep, color);
+    }
+    fn draw_landing_preview(&self) {
+        if self.level_state != LevelState::Playing || self.transition.is_some() {
+            return;
+        }
+        let Some(next_pin) = self.pin_gun.pins.last() else {
+            return;
+        };
+        let Some(prediction) = self
+            .spinner
+            .predict(&self.flying_pins, self.launch(*next_pin))
+        else {
+            return;
+        };
+        let angle = (self.spinner.angular_position + prediction.angle).get::<radian>();
+        let direction = Vec2::from_angle(angle);
+        let radius = self.spinner.radius.get::<meter>();
+        let base = direction * radius;
+        let tip = direction * (radius + 1.);
+        match prediction.collision {
+            Collision::Landed { .. } => {
+                let color = self.color_map.display(next_pin.color);
+                draw_line(base.x, base.y, tip.x, tip.y, 0.2, Color { a: 0.4, ..color });
+            }
+            // a cross where the pin would break
+            Collision::WrongSector | Collision::Blocked => {
+                draw_glyph(4, base.x, base.y, 0.5, Self::PREVIEW_MISS);
+            }
+        }
+    }
+    fn draw_background(&self, world_bounding_box_meters: Rect) {
+        let Rect { x, y, w, h } = world_bounding_box_meters;
+        match &self.theme.background {
+            Background::Solid { color } => clear_background(*color),
+            Background::Gradient { top, bottom } => {
+                clear_background(*bottom);
+                let vertices = vec![
+                    Vertex::new(x, y, 0., 0., 0., *bottom),
+                    Vertex::new(x + w, y, 0., 0., 0., *bottom),
+                    Vertex::new(x + w, y + h, 0., 0., 0., *top),
+                    Vertex::new(x, y + h, 0., 0., 0., *top),
+                ];
+                draw_mesh(&Mesh {
+                    vertices,
+                    indices: vec![0, 1, 2, 0, 2, 3],
+                    texture: None,
+                });
+            }
+            Background::Image { .. } => {
+                clear_background(colors::BLACK);
+                if let Some(texture) = &self.theme_assets.background {
+                    draw_texture_ex(
+                        texture,
+                        x,
+                        y,
+                        colors::WHITE,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(w, h)),
+                            flip_y: true,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+    }
+    // glyphs ride along with the spinner, one per sector and one past the tip of each pin
+    fn draw_spinner_glyphs(spinner: &Spinner, color_map: &ColorMap, scale: f32, rotation: Angle) {
+        let radius = spinner.radius.get::<meter>() * scale;
+        for sector in spinner.sectors.iter() {
+            let Some(glyph) = color_map.glyph(sector.color) else {
+                continue;
+            };
+            let angle = (rotation + (sector.angle_start + sector.angle_stop) / 2.).get::<radian>();
+            let position = Vec2::from_angle(angle) * radius * 0.6;
+            let glyph_color = if use_white_text(color_map.display(sector.color)) {
+                colors::WHITE
+            } else {
+                colors::BLACK
+            };
+            draw_glyph(glyph, position.x, position.y, 0.4 * scale, glyph_color);
+        }
+        for pin in spinner.pins.iter() {
+            let Some(glyph) = color_map.glyph(pin.color) else {
+                continue;
+            };
+            let angle = (rotation + pin.angular_position).get::<radian>();
+            let distance = radius + (pin.length.get::<meter>() + 0.25) * scale;
+            let position = Vec2::from_angle(angle) * distance;
+            draw_glyph(glyph, position.x, position.y, 0.3 * scale, colors::WHITE);
         }
     }
 }
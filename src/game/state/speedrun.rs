@@ -0,0 +1,158 @@
+use macroquad::miniquad::date::now;
+use serde_json::json;
+use uom::si::{f32::Time, time::second};
+
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+use crate::game::{Banner, GameMode, GameState, RunComparison};
+
+impl RunComparison {
+    // a best with a different number of splits came from an older version of the pack
+    fn new(splits: &[Time], best: Option<&Vec<Time>>) -> Self {
+        match best {
+            None => RunComparison::FirstRun,
+            Some(best) if best.len() != splits.len() => RunComparison::StaleBest,
+            Some(best) if splits.last() < best.last() => RunComparison::Faster,
+            Some(_) => RunComparison::Slower,
+        }
+    }
+}
+
+impl<'a> GameState<'a> {
+    pub(crate) const SPEEDRUN_SEED: u64 = 0x5049_4E57_4845_454C;
+
+    pub(crate) fn run_time(&self) -> Time {
+        self.level_stats
+            .iter()
+            .fold(Time::new::<second>(0.), |total, stats| total + stats.time)
+    }
+    pub(crate) fn run_assisted(&self) -> bool {
+        self.level_stats.iter().any(|stats| stats.assisted)
+    }
+    // assisted runs are compared with the best assisted run instead
+    fn saved_personal_best(&self) -> Option<&Vec<Time>> {
+        let personal_bests = if self.run_assisted() {
+            &self.save_data.assisted_personal_bests
+        } else {
+            &self.save_data.personal_bests
+        };
+        personal_bests.get(&self.pack_name)
+    }
+    // splits from before the pack changed its level count don't line up with this run's
+    pub(crate) fn personal_best(&self) -> Option<&Vec<Time>> {
+        self.saved_personal_best()
+            .filter(|best| best.len() == self.levels.len())
+    }
+    // difference between the latest split and the personal best at the same point in the run
+    pub(crate) fn split_delta(&self) -> Option<Time> {
+        let split_idx = self.splits.len().checked_sub(1)?;
+        let best_split = self.personal_best()?.get(split_idx)?;
+        Some(self.splits[split_idx] - *best_split)
+    }
+    pub(crate) fn record_split(&mut self) {
+        if self.game_mode != GameMode::Speedrun {
+            return;
+        }
+        self.splits.push(self.run_time());
+        if self.splits.len() < self.levels.len() {
+            return;
+        }
+        let assisted = self.run_assisted();
+        let message = match (
+            RunComparison::new(&self.splits, self.saved_personal_best()),
+            assisted,
+        ) {
+            (RunComparison::Slower, _) => return,
+            (RunComparison::StaleBest, false) => {
+                "The levels changed, so this run replaces the old personal best"
+            }
+            (RunComparison::StaleBest, true) => {
+                "The levels changed, so this run replaces the old assisted personal best"
+            }
+            (RunComparison::FirstRun | RunComparison::Faster, false) => "New personal best!",
+            (RunComparison::FirstRun | RunComparison::Faster, true) => {
+                "New assisted personal best!"
+            }
+        };
+        let personal_bests = if assisted {
+            &mut self.save_data.assisted_personal_bests
+        } else {
+            &mut self.save_data.personal_bests
+        };
+        personal_bests.insert(self.pack_name.clone(), self.splits.clone());
+        self.save_after_event = true;
+        self.event_banners.push(Banner::info(message));
+    }
+    pub(crate) fn export_splits(&mut self) {
+        if self.splits.is_empty() {
+            self.banner = Some(Banner::error("No splits to export yet"));
+            return;
+        }
+        let personal_best = self.personal_best();
+        let levels = self
+            .splits
+            .iter()
+            .enumerate()
+            .map(|(split_idx, split)| {
+                let previous = split_idx
+                    .checked_sub(1)
+                    .map_or(Time::new::<second>(0.), |idx| self.splits[idx]);
+                json!({
+                    "level": split_idx + 1,
+                    "split": split.get::<second>(),
+                    "segment": (*split - previous).get::<second>(),
+                    "personal_best_split": personal_best
+                        .and_then(|best| best.get(split_idx))
+                        .map(|best| best.get::<second>()),
+                })
+            })
+            .collect::<Vec<_>>();
+        let splits_str = serde_json::to_string_pretty(&json!({
+            "pack": self.pack_name,
+            "assisted": self.run_assisted(),
+            "levels": levels,
+        }))
+        .expect("splits always serialize");
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = format!("pinwheel-splits-{}-{}.json", self.pack_name, now() as u64);
+            self.banner = Some(match std::fs::write(&path, splits_str) {
+                Ok(()) => Banner::info(format!("Exported splits to {path}")),
+                Err(e) => Banner::error(format!("Failed to write {path}: {e}")),
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            web::clipboard_write(&splits_str);
+            self.banner = Some(Banner::info("Copied splits to clipboard"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splits(seconds: &[f32]) -> Vec<Time> {
+        seconds.iter().map(|&s| Time::new::<second>(s)).collect()
+    }
+
+    #[test]
+    fn only_faster_runs_beat_a_matching_best() {
+        let best = splits(&[10., 20.]);
+        let faster = RunComparison::new(&splits(&[9., 19.]), Some(&best));
+        assert_eq!(faster, RunComparison::Faster);
+        let slower = RunComparison::new(&splits(&[9., 21.]), Some(&best));
+        assert_eq!(slower, RunComparison::Slower);
+        assert_eq!(RunComparison::new(&best, None), RunComparison::FirstRun);
+    }
+
+    #[test]
+    fn bests_from_a_different_level_count_are_stale() {
+        let best = splits(&[10., 20.]);
+        let slower_with_more_levels = splits(&[15., 30., 45.]);
+        let comparison = RunComparison::new(&slower_with_more_levels, Some(&best));
+        assert_eq!(comparison, RunComparison::StaleBest);
+    }
+}
@@ -1,41 +1,137 @@
 use macroquad::prelude::*;
-use uom::si::f32::{Angle, Time};
+use uom::si::{f32::Time, time::second};
 
-use crate::game::{GameState, LevelState, Sounds, utils::normalize_angle};
+use crate::game::{
+    Banner, Collision, GameEvent, GameMode, GameState, LevelState, LevelStats, Spinner, Ui,
+};
 
 impl<'a> GameState<'a> {
+    // long stalls, like a backgrounded tab, skip ahead instead of simulating every step
+    const MAX_LAG_SECONDS: f32 = 0.25;
+
+    const SLOW_MOTION_SCALE: f32 = 0.5;
+
+    fn slow_motion_held() -> bool {
+        is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+    }
+    fn fire_pressed() -> bool {
+        is_key_pressed(KeyCode::Space) || is_mouse_button_pressed(MouseButton::Left)
+    }
+    // touches also show up as left clicks
+    fn any_input_pressed() -> bool {
+        get_last_key_pressed().is_some() || Self::fire_pressed()
+    }
     pub(crate) fn step(&mut self, dt: Time) {
+        self.ui.update();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.reload_levels(dt);
+
+        // wait for the first interaction so audio can start
+        if !self.audio_unlocked {
+            self.audio_unlocked = Self::any_input_pressed();
+            return;
+        }
+
+        // expire old banners
+        if let Some(banner) = self.banner.as_mut() {
+            banner.age += dt;
+            if banner.expired() {
+                self.banner = None;
+            }
+        }
+
+        self.mixer.update(dt, &self.save_data.settings);
+        self.particles.enabled = self.save_data.settings.particles;
+        self.color_map.palette = self.save_data.settings.palette;
+        self.particles.update(dt);
+        self.update_toasts(dt);
+
+        if self.update_name_entry() {
+            return;
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.save_data.settings.muted = !self.save_data.settings.muted;
+            if let Err(e) = self.save_data.save() {
+                self.banner = Some(Banner::error(e));
+            }
+        }
+
+        if self.update_leaderboard_screen()
+            || self.update_achievements_screen()
+            || self.update_settings_menu()
+        {
+            return;
+        }
+
+        // ignore input until the next level has finished animating in
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += dt;
+            if transition.is_finished() {
+                self.transition = None;
+            }
+            return;
+        }
+
+        // share levels via the clipboard
+        if is_key_pressed(KeyCode::C) {
+            self.copy_level_code();
+        }
+        if is_key_pressed(KeyCode::V) {
+            self.paste_level_code();
+        }
+        if is_key_pressed(KeyCode::E) && self.game_mode == GameMode::Speedrun {
+            self.export_splits();
+        }
+        // undoing takes the whole press, so a tap on the button doesn't also fire
+        let undo_clicked = !self.game_mode.is_ranked() && Ui::clicked(self.undo_button_rect());
+        if is_key_pressed(KeyCode::U) || undo_clicked {
+            self.undo_shot();
+            if undo_clicked {
+                return;
+            }
+        }
+
         // check win condition
         if self.pin_gun.pins.is_empty() && self.flying_pins.is_empty() {
             if self.level_state == LevelState::Playing {
                 if self.level_idx == self.levels.len() - 1 {
-                    self.play_sound(Sounds::WinGame);
+                    self.emit(GameEvent::GameWon);
                 } else {
-                    self.play_sound(Sounds::WinLevel);
+                    self.emit(GameEvent::LevelWon);
                 }
             }
             self.level_state = LevelState::Won;
         }
 
-        if is_key_pressed(KeyCode::Space) || is_mouse_button_pressed(MouseButton::Left) {
+        if Self::fire_pressed() {
             match self.level_state {
                 LevelState::Playing => {
                     // fire a pin if the player asked to
+                    self.snapshot_shot();
                     if let Some(next_pin) = self.pin_gun.pins.pop() {
-                        self.flying_pins.push(next_pin.into());
-                        self.play_sound(Sounds::PinFire);
+                        let pin = self.launch(next_pin);
+                        self.flying_pins.push(pin);
+                        let on_beat = self.rhythm_bpm().is_some_and(|bpm| {
+                            self.mixer.beat_offset(bpm) < LevelStats::ON_BEAT_SECONDS
+                        });
+                        self.emit(GameEvent::PinFired { on_beat });
                     }
                 }
                 LevelState::Won => {
                     if self.level_idx < self.levels.len() - 1 {
-                        self.play_sound(Sounds::NextLevel);
-                        self.level_idx += 1;
-                        self.load_level(self.level_idx);
+                        self.transition_to_level(self.level_idx + 1);
+                        self.save_progress();
+                        self.emit(GameEvent::LevelStarted { retry: false });
+                    } else {
+                        // a finished pack can be run again, so everyone at the desk gets a go
+                        self.start_new_run();
                     }
                 }
                 LevelState::Lost => {
-                    self.play_sound(Sounds::NextLevel);
-                    self.load_level(self.level_idx);
+                    if !self.restore_checkpoint() {
+                        self.transition_to_level(self.level_idx);
+                    }
+                    self.emit(GameEvent::LevelStarted { retry: true });
                 }
             }
         }
@@ -46,46 +142,65 @@ impl<'a> GameState<'a> {
             _ => (),
         }
 
-        // spin the spinner
-        let d_theta: Angle = (self.spinner.angular_velocity * dt).into();
-        self.spinner.angular_position = normalize_angle(&(self.spinner.angular_position + d_theta));
+        let settings = &self.save_data.settings;
+        let slow_motion = Self::slow_motion_held();
+        let rhythm_bpm = self.rhythm_bpm();
+        // rhythm mode counts too, since it changes how fast the spinner turns
+        let stats = &mut self.level_stats[self.level_idx];
+        stats.time += dt;
+        stats.assisted |= settings.assists_on() || slow_motion || rhythm_bpm.is_some();
 
-        // advance flying pins
-        for flying_pin in self.flying_pins.iter_mut() {
-            flying_pin.vertical_position += flying_pin.vertical_velocity * dt;
-        }
+        // in rhythm mode the spinner turns once every whole number of beats
+        let level_velocity =
+            self.levels[self.level_idx].spinner.angular_velocity * settings.spinner_speed;
+        self.spinner.blocker_hit_width = if settings.forgiving_blockers {
+            Spinner::FORGIVING_BLOCKER_WIDTH
+        } else {
+            Spinner::FULL_BLOCKER_WIDTH
+        };
+        self.spinner.angular_velocity = match rhythm_bpm {
+            Some(bpm) => Spinner::beat_synced_velocity(level_velocity, bpm),
+            None => level_velocity,
+        };
 
-        // check for collisions
-        let mut new_spinner_pin_idxs = Vec::new();
-        for (idx, flying_pin) in self.flying_pins.iter().enumerate() {
-            // sector collisions
-            for sector in self.spinner.sectors.iter() {
-                if self.spinner.pin_sector_collision(flying_pin, sector) {
-                    if flying_pin.color == sector.color {
-                        new_spinner_pin_idxs.push(idx);
-                    } else {
-                        self.play_sound(Sounds::LoseLevel);
-                        self.level_state = LevelState::Lost;
+        // physics runs in fixed steps so predictions play out exactly like the real thing
+        // slow motion only slows the simulation, so the clock and the music keep real time
+        let tick = Time::new::<second>(Spinner::TICK_SECONDS);
+        let sim_dt = if slow_motion {
+            dt * Self::SLOW_MOTION_SCALE
+        } else {
+            dt
+        };
+        self.physics_lag =
+            (self.physics_lag + sim_dt).min(Time::new::<second>(Self::MAX_LAG_SECONDS));
+        while self.physics_lag >= tick && self.level_state == LevelState::Playing {
+            self.physics_lag -= tick;
+            for contact in self.spinner.tick(&mut self.flying_pins) {
+                match contact.collision {
+                    Collision::Landed { sector, margin } => {
+                        let combo = self.combo;
+                        self.combo += 1;
+                        self.emit(GameEvent::PinLanded {
+                            sector,
+                            color: contact.pin.color,
+                            margin,
+                            combo,
+                        });
+                    }
+                    // a spare life only costs the pin that broke
+                    Collision::WrongSector | Collision::Blocked => {
+                        self.emit(GameEvent::PinBlocked { pin: contact.pin });
+                        if self.lives > 0 {
+                            self.lives -= 1;
+                            self.combo = 0;
+                            self.emit(GameEvent::LifeLost);
+                        } else {
+                            self.level_state = LevelState::Lost;
+                            self.emit(GameEvent::LevelLost);
+                        }
                     }
                 }
             }
-
-            // pin collisions
-            for spinner_pin in self.spinner.pins.iter() {
-                if self.spinner.pin_pin_collision(flying_pin, spinner_pin) {
-                    self.play_sound(Sounds::LoseLevel);
-                    self.level_state = LevelState::Lost;
-                }
-            }
-        }
-
-        // move pins that have landed safely into the spinner
-        for idx in new_spinner_pin_idxs.into_iter().rev() {
-            if !self.pin_gun.pins.is_empty() {
-                self.play_sound(Sounds::PinLand);
-            }
-            let new_spinner_pin = self.flying_pins.remove(idx);
-            self.spinner.take_pin(new_spinner_pin);
         }
     }
 }
@@ -0,0 +1,64 @@
+use std::{iter::Sum, ops::Add};
+
+use uom::si::{angle::degree, f32::Angle};
+
+use crate::game::{LevelStats, utils::format_time};
+
+impl LevelStats {
+    pub const CLOSE_CALL_MARGIN_DEGREES: f32 = 4.;
+    const POINTS_PER_PIN: u32 = 100;
+    const POINTS_PER_CLOSE_CALL: u32 = 50;
+    const POINTS_PER_RETRY: u32 = 500;
+    const POINTS_PER_ON_BEAT: u32 = 25;
+    pub const ON_BEAT_SECONDS: f32 = 0.08;
+
+    pub fn is_close_call(landing_margin: Angle) -> bool {
+        landing_margin < Angle::new::<degree>(Self::CLOSE_CALL_MARGIN_DEGREES)
+    }
+    // landing pins, cutting it close and firing on the beat earn points, while retries cost more
+    // than a pin is worth
+    pub fn score(&self) -> u32 {
+        (self.pins_landed * Self::POINTS_PER_PIN
+            + self.close_calls * Self::POINTS_PER_CLOSE_CALL
+            + self.on_beat * Self::POINTS_PER_ON_BEAT)
+            .saturating_sub(self.retries * Self::POINTS_PER_RETRY)
+    }
+    pub fn summary(&self) -> Vec<String> {
+        let mut summary = vec![
+            format!("time {}", format_time(self.time)),
+            format!("pins fired {}", self.pins_fired),
+            format!("retries {}", self.retries),
+            format!("close calls {}", self.close_calls),
+        ];
+        if self.on_beat > 0 {
+            summary.push(format!("on the beat {}", self.on_beat));
+        }
+        summary.push(format!("score {}", self.score()));
+        if self.assisted {
+            summary.push("assists used".into());
+        }
+        summary
+    }
+}
+
+impl Add for LevelStats {
+    type Output = LevelStats;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        LevelStats {
+            time: self.time + rhs.time,
+            pins_fired: self.pins_fired + rhs.pins_fired,
+            pins_landed: self.pins_landed + rhs.pins_landed,
+            retries: self.retries + rhs.retries,
+            close_calls: self.close_calls + rhs.close_calls,
+            on_beat: self.on_beat + rhs.on_beat,
+            assisted: self.assisted || rhs.assisted,
+        }
+    }
+}
+
+impl Sum for LevelStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(LevelStats::default(), Add::add)
+    }
+}
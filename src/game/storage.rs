@@ -0,0 +1,36 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::PathBuf};
+
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn storage_path(key: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pinwheel").join(format!("{key}.json")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+    fs::read_to_string(storage_path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn store(key: &str, value: &str) -> Result<(), String> {
+    let path = storage_path(key).ok_or("No data directory available")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    fs::write(&path, value).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+    web::storage_get(&format!("pinwheel.{key}"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn store(key: &str, value: &str) -> Result<(), String> {
+    web::storage_set(&format!("pinwheel.{key}"), value);
+    Ok(())
+}
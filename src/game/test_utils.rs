@@ -0,0 +1,90 @@
+// fixtures shared by the unit tests
+
+use uom::si::{
+    angle::revolution,
+    f32::{Angle, Length, Time},
+    length::meter,
+    time::second,
+};
+
+use crate::game::{
+    CalendarDate, ColorId, LeaderboardEntry, Level, PinFlying, PinInGun, PinOnSpinner, Sector,
+    Spinner,
+};
+
+pub fn built_in_levels() -> Vec<Level> {
+    Level::parse_pack(include_str!("../../assets/levels.json"), None)
+        .expect("built-in pack parses")
+        .levels
+}
+
+pub fn sector(color: u8, start: f32, stop: f32) -> Sector {
+    Sector {
+        color: ColorId(color),
+        angle_start: Angle::new::<revolution>(start),
+        angle_stop: Angle::new::<revolution>(stop),
+    }
+}
+
+// a blocker centered at the given angle, with both in revolutions
+pub fn blocker(position: f32, width: f32) -> PinOnSpinner {
+    PinOnSpinner {
+        color: ColorId(u8::MAX),
+        angular_position: Angle::new::<revolution>(position),
+        length: Length::new::<meter>(1.),
+        width: Angle::new::<revolution>(width),
+    }
+}
+
+// a spinner turned so that pins land at `landing` revolutions
+pub fn spinner_landing_at(sectors: Vec<Sector>, landing: f32) -> Spinner {
+    Spinner {
+        sectors,
+        angular_position: Angle::new::<revolution>(0.75 - landing),
+        ..Spinner::default()
+    }
+}
+
+// a pin just leaving the gun
+pub fn fired(color: u8) -> PinFlying {
+    PinInGun {
+        color: ColorId(color),
+    }
+    .into()
+}
+
+// a pin just inside the rim, where it touches sectors and blockers alike
+pub fn touching(color: u8) -> PinFlying {
+    PinFlying {
+        vertical_position: Length::new::<meter>(-1.9),
+        ..fired(color)
+    }
+}
+
+// two colors and a blocker, turned so pins land at `landing` revolutions
+pub fn mixed_spinner(landing: f32) -> Spinner {
+    let mut spinner = spinner_landing_at(
+        vec![sector(0, 0., 0.3), sector(1, 0.3, 0.7), sector(0, 0.7, 1.)],
+        landing,
+    );
+    spinner.pins.push(blocker(0.5, 0.05));
+    spinner
+}
+
+pub fn entry(name: &str, score: u32, seconds: f32) -> LeaderboardEntry {
+    LeaderboardEntry {
+        name: name.into(),
+        score,
+        time: Time::new::<second>(seconds),
+        recorded_at: 0.,
+        assisted: false,
+    }
+}
+
+pub fn names(entries: &[LeaderboardEntry]) -> Vec<&str> {
+    entries.iter().map(|entry| entry.name.as_str()).collect()
+}
+
+pub fn date(year: i64, month: u32, day: u32) -> CalendarDate {
+    CalendarDate { year, month, day }
+}
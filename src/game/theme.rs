@@ -0,0 +1,109 @@
+use std::{collections::HashMap, path::Path};
+
+use macroquad::{
+    file::load_string,
+    text::{Font, load_ttf_font},
+    texture::{Texture2D, load_texture},
+};
+
+use crate::game::{Background, Sprites, Theme, ThemeAssets};
+
+impl Theme {
+    pub const DEFAULT_NAME: &'static str = "classic";
+    pub const BUILT_IN: [(&'static str, &'static str); 3] = [
+        ("classic", include_str!("../../assets/themes/classic.json")),
+        (
+            "midnight",
+            include_str!("../../assets/themes/midnight.json"),
+        ),
+        ("paper", include_str!("../../assets/themes/paper.json")),
+    ];
+
+    fn built_in(name: &str) -> Option<&'static str> {
+        Self::BUILT_IN
+            .iter()
+            .find(|(built_in_name, _)| *built_in_name == name)
+            .map(|(_, theme_str)| *theme_str)
+    }
+    // built-in names pass through, anything else is a path relative to the file that mentions it
+    pub fn resolve_path(reference: &str, relative_to: Option<&str>) -> String {
+        if Self::built_in(reference).is_some() {
+            return reference.into();
+        }
+        match relative_to.and_then(|path| Path::new(path.split(['?', '#']).next()?).parent()) {
+            Some(dir) => dir.join(reference).to_string_lossy().into_owned(),
+            None => reference.into(),
+        }
+    }
+    // fonts are cached by path and leaked, since text parameters borrow them for the whole game
+    pub async fn load(
+        source: &str,
+        fonts: &mut HashMap<String, &'static Font>,
+    ) -> Result<(Theme, ThemeAssets), String> {
+        let (theme_str, base_path) = match Self::built_in(source) {
+            Some(theme_str) => (theme_str.to_string(), None),
+            None => (
+                load_string(source)
+                    .await
+                    .map_err(|e| format!("Failed to load theme {source}: {e}"))?,
+                Some(source),
+            ),
+        };
+        let theme = serde_json::from_str::<Theme>(&theme_str)
+            .map_err(|e| format!("Failed to parse theme {source}: {e}"))?;
+        let mut assets = ThemeAssets::default();
+        if let Background::Image { path } = &theme.background {
+            assets.background = Some(Self::load_sprite(path, base_path).await?);
+        }
+        theme.sprites.load(base_path, &mut assets).await?;
+        if let Some(path) = &theme.font {
+            let path = Self::resolve_path(path, base_path);
+            let font = match fonts.get(&path) {
+                Some(font) => *font,
+                None => {
+                    let font = load_ttf_font(&path)
+                        .await
+                        .map_err(|e| format!("Failed to load font {path}: {e}"))?;
+                    let font: &'static Font = Box::leak(Box::new(font));
+                    fonts.insert(path, font);
+                    font
+                }
+            };
+            assets.font = Some(font);
+        }
+        Ok((theme, assets))
+    }
+    pub async fn load_sprite(path: &str, base_path: Option<&str>) -> Result<Texture2D, String> {
+        let path = Self::resolve_path(path, base_path);
+        load_texture(&path)
+            .await
+            .map_err(|e| format!("Failed to load {path}: {e}"))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        serde_json::from_str(Self::built_in(Self::DEFAULT_NAME).unwrap())
+            .expect("built-in themes always parse")
+    }
+}
+
+impl Sprites {
+    // only replaces the sprites that are set, so a pack can override part of a theme
+    pub async fn load(
+        &self,
+        base_path: Option<&str>,
+        assets: &mut ThemeAssets,
+    ) -> Result<(), String> {
+        if let Some(path) = &self.spinner_texture {
+            assets.spinner_texture = Some(Theme::load_sprite(path, base_path).await?);
+        }
+        if let Some(path) = &self.gun_sprite {
+            assets.gun_sprite = Some(Theme::load_sprite(path, base_path).await?);
+        }
+        if let Some(path) = &self.pin_sprite {
+            assets.pin_sprite = Some(Theme::load_sprite(path, base_path).await?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{ColorMap, Spinner, Transition};
+
+impl Transition {
+    const SPIN_OUT_SECONDS: f32 = 0.4;
+    const SCALE_IN_SECONDS: f32 = 0.35;
+    const REFILL_SECONDS_PER_PIN: f32 = 0.03;
+
+    pub fn new(
+        old_spinner: Spinner,
+        old_color_map: ColorMap,
+        old_level_idx: usize,
+        pin_count: usize,
+    ) -> Transition {
+        Transition {
+            old_spinner,
+            old_color_map,
+            old_level_idx,
+            pin_count,
+            elapsed: Time::new::<second>(0.),
+        }
+    }
+    fn refill_seconds(&self) -> f32 {
+        self.pin_count as f32 * Self::REFILL_SECONDS_PER_PIN
+    }
+    pub fn is_finished(&self) -> bool {
+        self.elapsed.get::<second>()
+            >= Self::SPIN_OUT_SECONDS + Self::SCALE_IN_SECONDS.max(self.refill_seconds())
+    }
+    // progress through spinning out the old spinner, or None once it's gone
+    pub fn spin_out_progress(&self) -> Option<f32> {
+        let progress = self.elapsed.get::<second>() / Self::SPIN_OUT_SECONDS;
+        (progress < 1.).then_some(progress)
+    }
+    pub fn scale_in_progress(&self) -> f32 {
+        let seconds_in = self.elapsed.get::<second>() - Self::SPIN_OUT_SECONDS;
+        (seconds_in / Self::SCALE_IN_SECONDS).clamp(0., 1.)
+    }
+    pub fn loaded_pins(&self) -> usize {
+        let seconds_in = self.elapsed.get::<second>() - Self::SPIN_OUT_SECONDS;
+        ((seconds_in / Self::REFILL_SECONDS_PER_PIN).max(0.) as usize).min(self.pin_count)
+    }
+}
@@ -0,0 +1,110 @@
+use macroquad::{miniquad::window::screen_size, prelude::*};
+
+#[cfg(target_arch = "wasm32")]
+use crate::game::web;
+use crate::game::{Anchor, Insets, Ui, utils::draw_text_ex_center};
+
+impl Anchor {
+    // where the anchor sits within a rect, from (0, 0) at the top left to (1, 1) at the bottom
+    // right
+    fn fraction(&self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => vec2(0., 0.),
+            Anchor::Top => vec2(0.5, 0.),
+            Anchor::TopRight => vec2(1., 0.),
+            Anchor::Center => vec2(0.5, 0.5),
+            Anchor::BottomLeft => vec2(0., 1.),
+            Anchor::Bottom => vec2(0.5, 1.),
+        }
+    }
+}
+
+impl Insets {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn current() -> Insets {
+        Insets::default()
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn current() -> Insets {
+        let [top, right, bottom, left] = web::safe_area_insets();
+        Insets {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+}
+
+impl Ui {
+    pub const TARGET_WIDTH: f32 = 480.;
+    pub const TARGET_HEIGHT: f32 = 720.;
+
+    pub fn update(&mut self) {
+        let (width, height) = screen_size();
+        self.screen = vec2(width, height);
+        self.insets = Insets::current();
+        self.scale = (width / Self::TARGET_WIDTH).min(height / Self::TARGET_HEIGHT);
+    }
+    pub fn screen(&self) -> Rect {
+        Rect::new(0., 0., self.screen.x, self.screen.y)
+    }
+    pub fn safe_area(&self) -> Rect {
+        Rect::new(
+            self.insets.left,
+            self.insets.top,
+            self.screen.x - self.insets.left - self.insets.right,
+            self.screen.y - self.insets.top - self.insets.bottom,
+        )
+    }
+    // lengths are given at the target window size and scaled to the actual one
+    pub fn px(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+    pub fn anchor(&self, anchor: Anchor, offset: Vec2) -> Vec2 {
+        let safe_area = self.safe_area();
+        safe_area.point() + safe_area.size() * anchor.fraction() + offset * self.scale
+    }
+    // a rect whose matching corner or edge sits at the anchor point
+    pub fn rect(&self, anchor: Anchor, offset: Vec2, size: Vec2) -> Rect {
+        let size = size * self.scale;
+        let corner = self.anchor(anchor, offset) - size * anchor.fraction();
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    }
+    pub fn text_params<'a>(&self, size: f32, params: &TextParams<'a>) -> TextParams<'a> {
+        TextParams {
+            font_size: self.px(size).round().max(1.) as u16,
+            font_scale: 1.,
+            font_scale_aspect: 1.,
+            rotation: 0.,
+            ..params.clone()
+        }
+    }
+    // text lines up with the anchor horizontally and is centered on it vertically
+    pub fn text(&self, text: &str, anchor: Anchor, offset: Vec2, size: f32, params: &TextParams) {
+        let params = self.text_params(size, params);
+        let width = measure_text(text, params.font, params.font_size, params.font_scale).width;
+        let position = self.anchor(anchor, offset);
+        let x = position.x + width * (0.5 - anchor.fraction().x);
+        draw_text_ex_center(text, x, position.y, params);
+    }
+    pub fn text_at(&self, text: &str, position: Vec2, size: f32, params: &TextParams) {
+        draw_text_ex_center(text, position.x, position.y, self.text_params(size, params));
+    }
+    pub fn button(&self, rect: Rect, label: &str, highlighted: bool, params: &TextParams) {
+        let fill = if Self::hovered(rect) { 0.25 } else { 0.12 };
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::new(1., 1., 1., fill));
+        if highlighted {
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, self.px(2.), params.color);
+        }
+        // labels shrink to fit buttons squeezed onto short screens
+        let size = (rect.h / self.scale * 0.7).min(22.);
+        self.text_at(label, rect.center(), size, params);
+    }
+    pub fn hovered(rect: Rect) -> bool {
+        rect.contains(mouse_position().into())
+    }
+    pub fn clicked(rect: Rect) -> bool {
+        is_mouse_button_pressed(MouseButton::Left) && Self::hovered(rect)
+    }
+}
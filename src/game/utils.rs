@@ -1,62 +1,9 @@
 use macroquad::prelude::*;
-use uom::si::{angle::revolution, f32::Angle};
-
-pub(crate) struct CircularSectorParams {
-    pub(crate) n: u16,
-    pub(crate) radius: f32,
-    pub(crate) rotation: f32,
-    pub(crate) arc: f32,
-    pub(crate) color: Color,
-}
-
-pub(crate) fn draw_circular_sector(
-    x: f32,
-    y: f32,
-    params: CircularSectorParams,
-    gl: &mut InternalGlContext,
-) {
-    let CircularSectorParams {
-        n,
-        radius,
-        rotation,
-        arc,
-        color,
-    } = params;
-    if arc <= 0. || radius <= 0. || n == 0 {
-        return;
-    }
-    let step = arc / (n as f32);
-    let center = Vertex::new(x, y, 0., 0., 0., color);
-    let center_idx = 0;
-    let mut vertices = vec![center];
-    let mut indices = vec![center_idx];
-    let mut previous_vertex_idx = None;
-    let mut theta = rotation;
-    let mut i = 0;
-    while i <= n {
-        let new_vertex = Vertex::new(
-            radius * theta.cos() + x,
-            radius * theta.sin() + y,
-            0.,
-            0.,
-            0.,
-            color,
-        );
-        vertices.push(new_vertex);
-        let new_vertex_idx = (vertices.len() - 1) as u16;
-        if let Some(previous_vertex_idx) = previous_vertex_idx {
-            indices.extend_from_slice(&[center_idx, new_vertex_idx, previous_vertex_idx]);
-        }
-        previous_vertex_idx = Some(new_vertex_idx);
-        theta += step;
-        i += 1;
-    }
-    gl.flush();
-    gl.quad_gl.texture(None);
-    gl.quad_gl.draw_mode(DrawMode::Triangles);
-    gl.quad_gl.geometry(&vertices, &indices);
-    gl.flush();
-}
+use uom::si::{
+    angle::revolution,
+    f32::{Angle, Time},
+    time::second,
+};
 
 pub(crate) fn normalize_angle(angle: &Angle) -> Angle {
     let mut new_angle = *angle;
@@ -83,12 +30,62 @@ pub(crate) fn draw_text_ex_center(
     } = params;
     let dimensions = measure_text(text, font, font_size, font_scale);
     let new_x = x - dimensions.width / 2.;
-    let new_y = y - dimensions.height / 2.;
+    let new_y = y - dimensions.height / 2. + dimensions.offset_y;
     draw_text_ex(text, new_x, new_y, params)
 }
 
+pub(crate) fn wrap_text(text: &str, max_width: f32, params: &TextParams) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        let width =
+            measure_text(&candidate, params.font, params.font_size, params.font_scale).width;
+        if width > max_width && !line.is_empty() {
+            lines.push(line);
+            line = word.to_string();
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub(crate) fn format_time(time: Time) -> String {
+    let seconds = time.get::<second>();
+    format!("{}:{:05.2}", (seconds / 60.) as u32, seconds % 60.)
+}
+
 pub(crate) fn use_white_text(background: Color) -> bool {
     let Color { r, g, b, .. } = background;
     // https://stackoverflow.com/a/3943023
-    r * 0.299 + g * 0.587 + b * 0.114 > 165.
+    r * 0.299 + g * 0.587 + b * 0.114 < 186. / 255.
+}
+
+// a small shape that tells colors apart without relying on hue
+pub(crate) fn draw_glyph(glyph: usize, x: f32, y: f32, size: f32, color: Color) {
+    let r = size / 2.;
+    match glyph % 8 {
+        0 => draw_circle(x, y, r, color),
+        1 => draw_poly(x, y, 3, r, 90., color),
+        2 => draw_rectangle(x - r * 0.8, y - r * 0.8, r * 1.6, r * 1.6, color),
+        3 => draw_poly(x, y, 4, r, 0., color),
+        4 => {
+            draw_line(x - r, y - r, x + r, y + r, r * 0.5, color);
+            draw_line(x - r, y + r, x + r, y - r, r * 0.5, color);
+        }
+        5 => {
+            draw_rectangle(x - r, y - r * 0.25, r * 2., r * 0.5, color);
+            draw_rectangle(x - r * 0.25, y - r, r * 0.5, r * 2., color);
+        }
+        6 => draw_poly(x, y, 6, r, 0., color),
+        _ => draw_circle_lines(x, y, r * 0.8, r * 0.35, color),
+    }
 }
@@ -0,0 +1,37 @@
+use std::{fs, path::PathBuf};
+
+use uom::si::{f32::Time, time::second};
+
+use crate::game::{Level, LevelPack, LevelWatcher};
+
+impl LevelWatcher {
+    const POLL_INTERVAL_SECONDS: f32 = 0.25;
+
+    pub fn new(path: PathBuf) -> LevelWatcher {
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        LevelWatcher {
+            path,
+            modified,
+            time_since_poll: Time::new::<second>(0.),
+        }
+    }
+    pub fn poll(&mut self, dt: Time) -> Option<Result<LevelPack, String>> {
+        self.time_since_poll += dt;
+        if self.time_since_poll < Time::new::<second>(Self::POLL_INTERVAL_SECONDS) {
+            return None;
+        }
+        self.time_since_poll = Time::new::<second>(0.);
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(
+            fs::read_to_string(&self.path)
+                .map_err(|e| format!("Failed to read {}: {e}", self.path.display()))
+                .and_then(|levels_str| {
+                    Level::parse_pack(&levels_str, Some(&self.path.to_string_lossy()))
+                }),
+        )
+    }
+}
@@ -0,0 +1,52 @@
+unsafe extern "C" {
+    fn pinwheel_storage_get(key_ptr: *const u8, key_len: usize) -> i32;
+    fn pinwheel_storage_set(
+        key_ptr: *const u8,
+        key_len: usize,
+        value_ptr: *const u8,
+        value_len: usize,
+    );
+    fn pinwheel_query_param(name_ptr: *const u8, name_len: usize) -> i32;
+    fn pinwheel_take_string(dest_ptr: *mut u8);
+    fn pinwheel_clipboard_write(text_ptr: *const u8, text_len: usize);
+    fn pinwheel_safe_area_inset(side: u32) -> f32;
+}
+
+// lets the miniquad loader check that web/pinwheel.js matches this build
+#[unsafe(no_mangle)]
+pub extern "C" fn pinwheel_crate_version() -> u32 {
+    2
+}
+
+// the JS side stages a string and reports its length, then copies it into our buffer
+fn take_staged_string(len: i32) -> Option<String> {
+    if len < 0 {
+        return None;
+    }
+    let mut buffer = vec![0u8; len as usize];
+    unsafe { pinwheel_take_string(buffer.as_mut_ptr()) };
+    String::from_utf8(buffer).ok()
+}
+
+pub fn storage_get(key: &str) -> Option<String> {
+    let len = unsafe { pinwheel_storage_get(key.as_ptr(), key.len()) };
+    take_staged_string(len)
+}
+
+pub fn storage_set(key: &str, value: &str) {
+    unsafe { pinwheel_storage_set(key.as_ptr(), key.len(), value.as_ptr(), value.len()) };
+}
+
+pub fn query_param(name: &str) -> Option<String> {
+    let len = unsafe { pinwheel_query_param(name.as_ptr(), name.len()) };
+    take_staged_string(len)
+}
+
+pub fn clipboard_write(text: &str) {
+    unsafe { pinwheel_clipboard_write(text.as_ptr(), text.len()) };
+}
+
+// top, right, bottom and left, in canvas pixels
+pub fn safe_area_insets() -> [f32; 4] {
+    [0, 1, 2, 3].map(|side| unsafe { pinwheel_safe_area_inset(side) })
+}
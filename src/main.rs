@@ -2,13 +2,13 @@ use std::error::Error;
 
 use macroquad::prelude::*;
 
-use crate::game::GameState;
+use crate::game::{GameState, LaunchOptions};
 
 mod game;
 
 #[macroquad::main("Pinwheel")]
 async fn main() -> Result<(), Box<dyn Error>> {
     let gl = unsafe { get_internal_gl() };
-    let mut game = GameState::new(gl).await?;
+    let mut game = GameState::new(gl, LaunchOptions::from_env()).await?;
     game.run().await;
 }
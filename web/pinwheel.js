@@ -0,0 +1,74 @@
+"use strict";
+
+// Browser glue for Pinwheel. Load this after mq_js_bundle.js and before calling load().
+(function () {
+    let staged = null;
+    let safe_area_probe = null;
+
+    function read_string(ptr, len) {
+        return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+    }
+
+    function stage_string(value) {
+        if (value === null || value === undefined) {
+            staged = null;
+            return -1;
+        }
+        staged = new TextEncoder().encode(value);
+        return staged.length;
+    }
+
+    miniquad_add_plugin({
+        name: "pinwheel",
+        version: 2,
+        register_plugin: function (importObject) {
+            importObject.env.pinwheel_storage_get = function (key_ptr, key_len) {
+                try {
+                    return stage_string(window.localStorage.getItem(read_string(key_ptr, key_len)));
+                } catch (e) {
+                    console.warn("Local storage is unavailable", e);
+                    return stage_string(null);
+                }
+            };
+            importObject.env.pinwheel_storage_set = function (key_ptr, key_len, value_ptr, value_len) {
+                try {
+                    window.localStorage.setItem(
+                        read_string(key_ptr, key_len),
+                        read_string(value_ptr, value_len),
+                    );
+                } catch (e) {
+                    console.warn("Failed to write to local storage", e);
+                }
+            };
+            importObject.env.pinwheel_query_param = function (name_ptr, name_len) {
+                let params = new URLSearchParams(window.location.search);
+                return stage_string(params.get(read_string(name_ptr, name_len)));
+            };
+            importObject.env.pinwheel_clipboard_write = function (text_ptr, text_len) {
+                let text = read_string(text_ptr, text_len);
+                navigator.clipboard.writeText(text).catch(function (e) {
+                    console.warn("Failed to write to clipboard", e);
+                });
+            };
+            // side is 0 to 3 for top, right, bottom and left, and the result is in canvas pixels
+            importObject.env.pinwheel_safe_area_inset = function (side) {
+                if (safe_area_probe === null) {
+                    safe_area_probe = document.createElement("div");
+                    safe_area_probe.style.cssText = "position: fixed; visibility: hidden; " +
+                        "padding: env(safe-area-inset-top) env(safe-area-inset-right) " +
+                        "env(safe-area-inset-bottom) env(safe-area-inset-left);";
+                    document.body.appendChild(safe_area_probe);
+                }
+                let style = window.getComputedStyle(safe_area_probe);
+                let padding = [style.paddingTop, style.paddingRight, style.paddingBottom, style.paddingLeft];
+                let canvas = document.getElementById("glcanvas");
+                let pixel_ratio = canvas.clientWidth > 0 ? canvas.width / canvas.clientWidth : 1;
+                return (parseFloat(padding[side]) || 0) * pixel_ratio;
+            };
+            importObject.env.pinwheel_take_string = function (dest_ptr) {
+                new Uint8Array(wasm_memory.buffer, dest_ptr, staged.length).set(staged);
+                staged = null;
+            };
+        },
+    });
+})();